@@ -1,21 +1,227 @@
 use std::collections::HashMap;
 
 use pyo3::{
+    exceptions::PyIndexError,
     prelude::*,
-    types::{PyBytes, PyType},
+    types::{PyBytes, PyTuple, PyType},
 };
-use vodozemac::olm::SessionConfig;
+use serde_json::{json, Map, Value};
+use vodozemac::{olm::SessionConfig, KeyId};
 
-use super::session::Session;
+use super::session::{OlmSessionConfig, Session};
 use crate::{
+    canonical_json::JsonObject,
     convert_to_pybytes,
-    error::{LibolmPickleError, PickleError, SessionError},
+    error::{
+        DehydratedDeviceError, JsonSignatureError, LibolmPickleError, PickleError, SessionError,
+    },
     types::{Curve25519PublicKey, Ed25519PublicKey, Ed25519Signature, PreKeyMessage},
 };
 
+const DEFAULT_ALGORITHMS: [&str; 2] = ["m.olm.v1.curve25519-aes-sha2", "m.megolm.v1.aes-sha2"];
+
+/// The result of generating one-time keys, containing the public part of the
+/// keys which were created and the ones which had to be discarded to make space
+/// for them.
+#[pyclass]
+pub struct OneTimeKeyGenerationResult {
+    inner: vodozemac::olm::OneTimeKeyGenerationResult,
+    created_key_ids: Vec<String>,
+    removed_key_ids: Vec<Option<String>>,
+}
+
+#[pymethods]
+impl OneTimeKeyGenerationResult {
+    /// The newly created one-time keys.
+    #[getter]
+    fn created(&self) -> Vec<Curve25519PublicKey> {
+        self.inner.created.iter().map(|&k| k.into()).collect()
+    }
+
+    /// The oldest one-time keys, which were discarded because the account
+    /// already stored the maximum number of private one-time keys.
+    #[getter]
+    fn removed(&self) -> Vec<Curve25519PublicKey> {
+        self.inner.removed.iter().map(|&k| k.into()).collect()
+    }
+
+    /// The base64-encoded IDs of the newly created one-time keys, in the same
+    /// order as `created`.
+    #[getter]
+    fn created_key_ids(&self) -> Vec<String> {
+        self.created_key_ids.clone()
+    }
+
+    /// The base64-encoded IDs of the discarded one-time keys, in the same
+    /// order as `removed`.
+    ///
+    /// The ID is `None` if the key was already marked as published, since
+    /// vodozemac doesn't expose the IDs of the published keys.
+    #[getter]
+    fn removed_key_ids(&self) -> Vec<Option<String>> {
+        self.removed_key_ids.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "OneTimeKeyGenerationResult(created={}, removed={})",
+            self.inner.created.len(),
+            self.inner.removed.len()
+        )
+    }
+}
+
+/// Which of our keys the sender of a pre-key message used to establish the
+/// session.
+pub(crate) struct UsedKey {
+    /// The base64-encoded ID of the key, `None` if the ID isn't known.
+    pub(crate) key_id: Option<String>,
+    pub(crate) fallback: bool,
+}
+
+/// The result of creating an inbound Olm session, containing the session, the
+/// decrypted plaintext of the pre-key message and which of our keys the sender
+/// used to establish the session.
+///
+/// For backwards compatibility, the result behaves like a
+/// `(session, plaintext)` tuple, it can be unpacked and indexed.
+#[pyclass]
+pub struct InboundCreationResult {
+    session: Py<Session>,
+    plaintext: Py<PyBytes>,
+    used_key: UsedKey,
+}
+
+#[pymethods]
+impl InboundCreationResult {
+    #[getter]
+    fn session(&self, py: Python<'_>) -> Py<Session> {
+        self.session.clone_ref(py)
+    }
+
+    #[getter]
+    fn plaintext(&self, py: Python<'_>) -> Py<PyBytes> {
+        self.plaintext.clone_ref(py)
+    }
+
+    /// The base64-encoded ID of the one-time key or the fallback key which
+    /// was used to establish the session.
+    ///
+    /// This is `None` if the key was already marked as published, since
+    /// vodozemac doesn't expose the IDs of the published keys.
+    #[getter]
+    fn used_key_id(&self) -> Option<&str> {
+        self.used_key.key_id.as_deref()
+    }
+
+    /// Was the session established using our fallback key instead of a
+    /// one-time key, in which case a new fallback key should be generated.
+    #[getter]
+    fn used_fallback_key(&self) -> bool {
+        self.used_key.fallback
+    }
+
+    fn __len__(&self) -> usize {
+        2
+    }
+
+    fn __getitem__(&self, py: Python<'_>, index: isize) -> PyResult<Py<PyAny>> {
+        match index {
+            0 | -2 => Ok(self.session.clone_ref(py).into_any()),
+            1 | -1 => Ok(self.plaintext.clone_ref(py).into_any()),
+            _ => Err(PyIndexError::new_err("InboundCreationResult index out of range")),
+        }
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let tuple = PyTuple::new(
+            py,
+            [self.session.clone_ref(py).into_any(), self.plaintext.clone_ref(py).into_any()],
+        )?;
+
+        Ok(tuple.try_iter()?.into_any())
+    }
+}
+
 #[pyclass]
 pub struct Account {
-    inner: vodozemac::olm::Account,
+    pub(crate) inner: vodozemac::olm::Account,
+}
+
+impl Account {
+    fn sign_object(
+        &self,
+        object: Value,
+        user_id: &str,
+        device_id: &str,
+    ) -> Result<JsonObject, JsonSignatureError> {
+        let Value::Object(object) = object else {
+            return Err(JsonSignatureError::NotAnObject);
+        };
+        let mut object = JsonObject::new(object);
+
+        let signature = self.inner.sign(object.canonical_json()?);
+        object.add_signature(user_id, &format!("ed25519:{device_id}"), signature.to_base64())?;
+
+        Ok(object)
+    }
+
+    fn signed_curve25519_keys(
+        &self,
+        py: Python<'_>,
+        keys: HashMap<KeyId, vodozemac::Curve25519PublicKey>,
+        fallback: bool,
+        user_id: &str,
+        device_id: &str,
+    ) -> Result<Py<PyAny>, JsonSignatureError> {
+        let mut signed_keys = Map::new();
+
+        for (key_id, key) in keys {
+            let mut key = json!({ "key": key.to_base64() });
+            if fallback {
+                key["fallback"] = Value::Bool(true);
+            }
+
+            signed_keys.insert(
+                format!("signed_curve25519:{}", key_id.to_base64()),
+                Value::Object(self.sign_object(key, user_id, device_id)?.inner),
+            );
+        }
+
+        Ok(JsonObject::new(signed_keys).into_py(py)?.unbind())
+    }
+
+    /// Find the ID of the one-time key or the fallback key with the given
+    /// public part.
+    ///
+    /// vodozemac only exposes the IDs of the unpublished keys, so this is
+    /// `None` for keys which were already marked as published.
+    fn key_id(&self, public_key: &vodozemac::Curve25519PublicKey) -> Option<KeyId> {
+        self.inner
+            .one_time_keys()
+            .into_iter()
+            .chain(self.inner.fallback_key())
+            .find_map(|(key_id, key)| (key == *public_key).then_some(key_id))
+    }
+
+    /// Create an inbound session from a pre-key message, along with the key
+    /// the sender used to establish it.
+    pub(crate) fn create_inbound(
+        &mut self,
+        identity_key: vodozemac::Curve25519PublicKey,
+        message: &vodozemac::olm::PreKeyMessage,
+    ) -> Result<(vodozemac::olm::InboundCreationResult, UsedKey), SessionError> {
+        let key_id = self.key_id(&message.one_time_key());
+        let stored_key_count = self.inner.stored_one_time_key_count();
+
+        let result = self.inner.create_inbound_session(identity_key, message)?;
+
+        // The used one-time key is removed from the account, while the
+        // fallback keys are kept.
+        let fallback = self.inner.stored_one_time_key_count() == stored_key_count;
+
+        Ok((result, UsedKey { key_id: key_id.map(|k| k.to_base64()), fallback }))
+    }
 }
 
 #[pymethods]
@@ -59,6 +265,40 @@ impl Account {
         Ok(self.inner.pickle().encrypt(pickle_key))
     }
 
+    /// Create an `Account` from a dehydrated device, as described in MSC3814.
+    ///
+    /// The `ciphertext` and `nonce` are the ones returned by
+    /// `to_dehydrated_device`, the `key` must be the same 32 byte key that was
+    /// used to create the dehydrated device.
+    #[classmethod]
+    fn from_dehydrated_device(
+        _cls: &Bound<'_, PyType>,
+        ciphertext: &str,
+        nonce: &str,
+        key: &[u8],
+    ) -> Result<Self, DehydratedDeviceError> {
+        let key: &[u8; 32] =
+            key.try_into().map_err(|_| DehydratedDeviceError::InvalidKeySize(key.len()))?;
+
+        let inner = vodozemac::olm::Account::from_dehydrated_device(ciphertext, nonce, key)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Export the `Account` as a dehydrated device, as described in MSC3814.
+    ///
+    /// The account must be a newly created one, since the dehydrated device
+    /// format doesn't contain any sessions. Returns the base64-encoded
+    /// ciphertext and nonce.
+    fn to_dehydrated_device(&self, key: &[u8]) -> Result<(String, String), DehydratedDeviceError> {
+        let key: &[u8; 32] =
+            key.try_into().map_err(|_| DehydratedDeviceError::InvalidKeySize(key.len()))?;
+
+        let result = self.inner.to_dehydrated_device(key)?;
+
+        Ok((result.ciphertext, result.nonce))
+    }
+
     #[getter]
     fn ed25519_key(&self) -> Ed25519PublicKey {
         self.inner.ed25519_key().into()
@@ -73,6 +313,77 @@ impl Account {
         self.inner.sign(message).into()
     }
 
+    /// Sign a JSON object, given as a dict or a JSON string, following the
+    /// Matrix signing rules.
+    ///
+    /// The signature is added to the `signatures` field of the object under
+    /// the given user ID and key ID, e.g. `ed25519:DEVICEID`. The signed
+    /// object is returned in the same form it was given in.
+    fn sign_json<'py>(
+        &self,
+        object: &Bound<'py, PyAny>,
+        user_id: &str,
+        key_id: &str,
+    ) -> Result<Bound<'py, PyAny>, JsonSignatureError> {
+        let py = object.py();
+        let mut object = JsonObject::extract(object)?;
+
+        let signature = self.inner.sign(object.canonical_json()?);
+        object.add_signature(user_id, key_id, signature.to_base64())?;
+
+        object.into_py(py)
+    }
+
+    /// Create the signed device keys of this account, in the form expected by
+    /// the `/keys/upload` endpoint.
+    #[pyo3(signature = (user_id, device_id, algorithms = None))]
+    fn device_keys(
+        &self,
+        py: Python<'_>,
+        user_id: &str,
+        device_id: &str,
+        algorithms: Option<Vec<String>>,
+    ) -> Result<Py<PyAny>, JsonSignatureError> {
+        let algorithms = algorithms
+            .unwrap_or_else(|| DEFAULT_ALGORITHMS.iter().map(|a| a.to_string()).collect());
+
+        let device_keys = json!({
+            "user_id": user_id,
+            "device_id": device_id,
+            "algorithms": algorithms,
+            "keys": {
+                format!("curve25519:{device_id}"): self.inner.curve25519_key().to_base64(),
+                format!("ed25519:{device_id}"): self.inner.ed25519_key().to_base64(),
+            },
+        });
+
+        Ok(self.sign_object(device_keys, user_id, device_id)?.into_py(py)?.unbind())
+    }
+
+    /// Create the signed, unpublished, one-time keys of this account, in the
+    /// form expected by the `one_time_keys` field of the `/keys/upload`
+    /// endpoint.
+    fn signed_one_time_keys(
+        &self,
+        py: Python<'_>,
+        user_id: &str,
+        device_id: &str,
+    ) -> Result<Py<PyAny>, JsonSignatureError> {
+        self.signed_curve25519_keys(py, self.inner.one_time_keys(), false, user_id, device_id)
+    }
+
+    /// Create the signed, unpublished, fallback key of this account, in the
+    /// form expected by the `fallback_keys` field of the `/keys/upload`
+    /// endpoint.
+    fn signed_fallback_keys(
+        &self,
+        py: Python<'_>,
+        user_id: &str,
+        device_id: &str,
+    ) -> Result<Py<PyAny>, JsonSignatureError> {
+        self.signed_curve25519_keys(py, self.inner.fallback_key(), true, user_id, device_id)
+    }
+
     #[getter]
     fn one_time_keys(&self) -> HashMap<String, Curve25519PublicKey> {
         self.inner.one_time_keys().into_iter().map(|(k, v)| (k.to_base64(), v.into())).collect()
@@ -83,8 +394,44 @@ impl Account {
         self.inner.max_number_of_one_time_keys()
     }
 
-    fn generate_one_time_keys(&mut self, count: usize) {
-        self.inner.generate_one_time_keys(count);
+    /// The number of one-time keys stored in the account, both the published
+    /// and the unpublished ones.
+    #[getter]
+    fn stored_one_time_key_count(&self) -> usize {
+        self.inner.stored_one_time_key_count()
+    }
+
+    fn generate_one_time_keys(&mut self, count: usize) -> OneTimeKeyGenerationResult {
+        // The created keys are unpublished, the removed ones might have been
+        // unpublished before they were removed.
+        let previous_keys = self.inner.one_time_keys();
+        let result = self.inner.generate_one_time_keys(count);
+
+        let key_ids: HashMap<_, _> = previous_keys
+            .into_iter()
+            .chain(self.inner.one_time_keys())
+            .map(|(key_id, key)| (key, key_id.to_base64()))
+            .collect();
+
+        let created_key_ids =
+            result.created.iter().filter_map(|key| key_ids.get(key).cloned()).collect();
+        let removed_key_ids = result.removed.iter().map(|key| key_ids.get(key).cloned()).collect();
+
+        OneTimeKeyGenerationResult { inner: result, created_key_ids, removed_key_ids }
+    }
+
+    /// Generate enough one-time keys to top up the ones on the server to half
+    /// of `max_number_of_one_time_keys`.
+    ///
+    /// The `server_count` is the `signed_curve25519` count of the
+    /// `one_time_key_counts` the server reported, the keys which were
+    /// generated but not published yet are counted as well.
+    fn replenish_one_time_keys(&mut self, server_count: usize) -> OneTimeKeyGenerationResult {
+        let target = self.inner.max_number_of_one_time_keys() / 2;
+        let count =
+            target.saturating_sub(server_count).saturating_sub(self.inner.one_time_keys().len());
+
+        self.generate_one_time_keys(count)
     }
 
     #[getter]
@@ -96,17 +443,30 @@ impl Account {
         self.inner.generate_fallback_key();
     }
 
+    /// Forget the previous fallback key, which is kept after a new fallback
+    /// key is generated, in case a message using it is still in flight.
+    ///
+    /// Returns `True` if there was a previous fallback key.
+    fn forget_fallback_key(&mut self) -> bool {
+        self.inner.forget_fallback_key()
+    }
+
     fn mark_keys_as_published(&mut self) {
         self.inner.mark_keys_as_published()
     }
 
+    #[pyo3(signature = (identity_key, one_time_key, session_config = None))]
     fn create_outbound_session(
         &self,
         identity_key: &Curve25519PublicKey,
         one_time_key: &Curve25519PublicKey,
+        session_config: Option<OlmSessionConfig>,
     ) -> Session {
+        let session_config =
+            session_config.map(|c| c.inner).unwrap_or_else(SessionConfig::version_1);
+
         let session = self.inner.create_outbound_session(
-            SessionConfig::version_1(),
+            session_config,
             identity_key.inner,
             one_time_key.inner,
         );
@@ -114,13 +474,20 @@ impl Account {
         Session { inner: session }
     }
 
+    /// Create an inbound session from a pre-key message, this removes the
+    /// one-time key which was used from the account.
     fn create_inbound_session(
         &mut self,
+        py: Python<'_>,
         identity_key: &Curve25519PublicKey,
         message: &PreKeyMessage,
-    ) -> Result<(Session, Py<PyBytes>), SessionError> {
-        let result = self.inner.create_inbound_session(identity_key.inner, &message.inner)?;
+    ) -> Result<InboundCreationResult, SessionError> {
+        let (result, used_key) = self.create_inbound(identity_key.inner, &message.inner)?;
 
-        Ok((Session { inner: result.session }, convert_to_pybytes(result.plaintext.as_slice())))
+        Ok(InboundCreationResult {
+            session: Py::new(py, Session { inner: result.session })?,
+            plaintext: convert_to_pybytes(result.plaintext.as_slice()),
+            used_key,
+        })
     }
 }
@@ -0,0 +1,189 @@
+use aes::{
+    cipher::{KeyIvInit, StreamCipher},
+    Aes256,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use pyo3::{prelude::*, types::PyBytes};
+use rand::{thread_rng, RngCore};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::{canonical_json::JsonObject, convert_to_pybytes, error::AttachmentError};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+const VERSION: &str = "v2";
+const ALGORITHM: &str = "A256CTR";
+const KEY_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+
+/// The state of an attachment which is being encrypted or decrypted.
+struct Cipher {
+    aes: Aes256Ctr,
+    sha256: Sha256,
+}
+
+impl Cipher {
+    fn new(key: &[u8; KEY_SIZE], iv: &[u8; IV_SIZE]) -> Self {
+        Self { aes: Aes256Ctr::new(key.into(), iv.into()), sha256: Sha256::new() }
+    }
+}
+
+/// Encrypts an attachment, e.g. an image or a file, chunk by chunk, using the
+/// `v2` [attachment encryption] of encrypted rooms.
+///
+/// Once all the chunks have been encrypted, `finalize()` returns the
+/// `EncryptedFile` metadata, which needs to be sent with the `url` of the
+/// uploaded attachment.
+///
+/// [attachment encryption]: https://spec.matrix.org/v1.11/client-server-api/#sending-encrypted-attachments
+#[pyclass]
+pub struct AttachmentEncryptor {
+    inner: Option<Cipher>,
+    key: [u8; KEY_SIZE],
+    iv: [u8; IV_SIZE],
+}
+
+#[pymethods]
+impl AttachmentEncryptor {
+    /// Create a new encryptor, using a random key and IV.
+    #[new]
+    fn new() -> Self {
+        let mut rng = thread_rng();
+
+        let mut key = [0u8; KEY_SIZE];
+        rng.fill_bytes(&mut key);
+
+        // Only the first 8 bytes of the IV are random, the lower 64 bits are
+        // used as the counter.
+        let mut iv = [0u8; IV_SIZE];
+        rng.fill_bytes(&mut iv[..8]);
+
+        Self { inner: Some(Cipher::new(&key, &iv)), key, iv }
+    }
+
+    /// Encrypt the next chunk of the attachment.
+    fn update(&mut self, chunk: &[u8]) -> Result<Py<PyBytes>, AttachmentError> {
+        let cipher = self.inner.as_mut().ok_or(AttachmentError::Finalized)?;
+
+        let mut ciphertext = chunk.to_vec();
+        cipher.aes.apply_keystream(&mut ciphertext);
+        cipher.sha256.update(&ciphertext);
+
+        Ok(convert_to_pybytes(&ciphertext))
+    }
+
+    /// Finish the encryption, returning the `EncryptedFile` metadata without
+    /// the `url`.
+    fn finalize<'py>(&mut self, py: Python<'py>) -> Result<Bound<'py, PyAny>, AttachmentError> {
+        let cipher = self.inner.take().ok_or(AttachmentError::Finalized)?;
+        let sha256 = cipher.sha256.finalize();
+
+        let metadata = json!({
+            "v": VERSION,
+            "key": {
+                "kty": "oct",
+                "key_ops": ["encrypt", "decrypt"],
+                "alg": ALGORITHM,
+                "k": URL_SAFE_NO_PAD.encode(self.key),
+                "ext": true,
+            },
+            "iv": vodozemac::base64_encode(self.iv),
+            "hashes": {
+                "sha256": vodozemac::base64_encode(sha256),
+            },
+        });
+
+        let Value::Object(metadata) = metadata else {
+            unreachable!("the metadata is always a JSON object");
+        };
+
+        Ok(JsonObject::new(metadata).into_py(py)?)
+    }
+}
+
+/// Decrypts an attachment chunk by chunk, using its `EncryptedFile` metadata.
+///
+/// The SHA-256 hash of the attachment is only checked once `finalize()` is
+/// called, the decrypted chunks must not be used before that.
+#[pyclass]
+pub struct AttachmentDecryptor {
+    inner: Option<Cipher>,
+    expected_sha256: Vec<u8>,
+}
+
+#[pymethods]
+impl AttachmentDecryptor {
+    /// Create a new decryptor from the `EncryptedFile` metadata of the
+    /// attachment.
+    #[new]
+    fn new(encrypted_file: &Bound<'_, PyAny>) -> Result<Self, AttachmentError> {
+        let encrypted_file = JsonObject::extract(encrypted_file)?.inner;
+
+        let version = get_str(&encrypted_file, "v")?;
+
+        if version != VERSION {
+            return Err(AttachmentError::UnsupportedVersion(version.to_owned()));
+        }
+
+        let key = encrypted_file
+            .get("key")
+            .and_then(Value::as_object)
+            .ok_or(AttachmentError::MissingField("key"))?;
+
+        let algorithm = get_str(key, "alg")?;
+
+        if algorithm != ALGORITHM || get_str(key, "kty")? != "oct" {
+            return Err(AttachmentError::UnsupportedAlgorithm(algorithm.to_owned()));
+        }
+
+        let key = URL_SAFE_NO_PAD.decode(get_str(key, "k")?.trim_end_matches('='))?;
+        let key: [u8; KEY_SIZE] =
+            key.as_slice().try_into().map_err(|_| AttachmentError::InvalidKeyLength(key.len()))?;
+
+        let iv = vodozemac::base64_decode(get_str(&encrypted_file, "iv")?)?;
+        let iv: [u8; IV_SIZE] =
+            iv.as_slice().try_into().map_err(|_| AttachmentError::InvalidIvLength(iv.len()))?;
+
+        let hashes = encrypted_file
+            .get("hashes")
+            .and_then(Value::as_object)
+            .ok_or(AttachmentError::MissingField("hashes"))?;
+        let expected_sha256 = vodozemac::base64_decode(get_str(hashes, "sha256")?)?;
+
+        Ok(Self { inner: Some(Cipher::new(&key, &iv)), expected_sha256 })
+    }
+
+    /// Decrypt the next chunk of the attachment.
+    fn update(&mut self, chunk: &[u8]) -> Result<Py<PyBytes>, AttachmentError> {
+        let cipher = self.inner.as_mut().ok_or(AttachmentError::Finalized)?;
+
+        cipher.sha256.update(chunk);
+        let mut plaintext = chunk.to_vec();
+        cipher.aes.apply_keystream(&mut plaintext);
+
+        Ok(convert_to_pybytes(&plaintext))
+    }
+
+    /// Finish the decryption, checking the SHA-256 hash of the attachment.
+    ///
+    /// Raises an `AttachmentHashMismatchException` if the hash doesn't match.
+    fn finalize(&mut self) -> Result<(), AttachmentError> {
+        let cipher = self.inner.take().ok_or(AttachmentError::Finalized)?;
+        let sha256 = cipher.sha256.finalize();
+
+        if bool::from(sha256.as_slice().ct_eq(&self.expected_sha256)) {
+            Ok(())
+        } else {
+            Err(AttachmentError::HashMismatch)
+        }
+    }
+}
+
+fn get_str<'a>(
+    object: &'a Map<String, Value>,
+    field: &'static str,
+) -> Result<&'a str, AttachmentError> {
+    object.get(field).and_then(Value::as_str).ok_or(AttachmentError::MissingField(field))
+}
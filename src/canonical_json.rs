@@ -0,0 +1,115 @@
+use pyo3::{prelude::*, types::PyString};
+use serde_json::{Map, Value};
+
+use crate::error::JsonSignatureError;
+
+/// The range of integers which are allowed in canonical JSON.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// A JSON object which was converted from a Python object, used to sign and
+/// verify JSON following the Matrix [signing rules].
+///
+/// The object remembers if it was given as a JSON string or as a Python
+/// object, so it can be converted back into the same form.
+///
+/// [signing rules]: https://spec.matrix.org/v1.11/appendices/#signing-json
+pub(crate) struct JsonObject {
+    pub(crate) inner: Map<String, Value>,
+    is_string: bool,
+}
+
+impl JsonObject {
+    /// Create a `JsonObject` which will be converted into a Python `dict`.
+    pub(crate) fn new(inner: Map<String, Value>) -> Self {
+        Self { inner, is_string: false }
+    }
+
+    /// Convert a Python `dict` or a JSON string into a `JsonObject`.
+    pub(crate) fn extract(object: &Bound<'_, PyAny>) -> Result<Self, JsonSignatureError> {
+        let is_string = object.is_instance_of::<PyString>();
+
+        let json: String = if is_string {
+            object.extract()?
+        } else {
+            object.py().import("json")?.call_method1("dumps", (object,))?.extract()?
+        };
+
+        match serde_json::from_str(&json)? {
+            Value::Object(inner) => Ok(Self { inner, is_string }),
+            _ => Err(JsonSignatureError::NotAnObject),
+        }
+    }
+
+    /// Convert the `JsonObject` back into the form it was given in.
+    pub(crate) fn into_py<'py>(
+        self,
+        py: Python<'py>,
+    ) -> Result<Bound<'py, PyAny>, JsonSignatureError> {
+        let json = serde_json::to_string(&self.inner)?;
+
+        if self.is_string {
+            Ok(PyString::new(py, &json).into_any())
+        } else {
+            Ok(py.import("json")?.call_method1("loads", (json,))?)
+        }
+    }
+
+    /// Get the canonical JSON form of this object, without the `signatures`
+    /// and `unsigned` fields.
+    pub(crate) fn canonical_json(&self) -> Result<String, JsonSignatureError> {
+        let mut object = self.inner.clone();
+        object.remove("signatures");
+        object.remove("unsigned");
+
+        let object = Value::Object(object);
+        check_canonical(&object)?;
+
+        // serde_json's `Map` is sorted by key, unless the `preserve_order`
+        // feature is enabled, and the compact formatter doesn't emit any
+        // insignificant whitespace.
+        Ok(serde_json::to_string(&object)?)
+    }
+
+    /// Get the base64-encoded signature of the given user and key ID.
+    pub(crate) fn signature(&self, user_id: &str, key_id: &str) -> Option<&str> {
+        self.inner.get("signatures")?.get(user_id)?.get(key_id)?.as_str()
+    }
+
+    /// Add a base64-encoded signature to the `signatures` field of the object.
+    pub(crate) fn add_signature(
+        &mut self,
+        user_id: &str,
+        key_id: &str,
+        signature: String,
+    ) -> Result<(), JsonSignatureError> {
+        let signatures = self
+            .inner
+            .entry("signatures")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or(JsonSignatureError::InvalidSignatures)?;
+
+        signatures
+            .entry(user_id)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or(JsonSignatureError::InvalidSignatures)?
+            .insert(key_id.to_owned(), Value::String(signature));
+
+        Ok(())
+    }
+}
+
+/// Check that the value only contains numbers which are allowed in canonical
+/// JSON, that is integers in the range [-(2**53)+1, (2**53)-1].
+fn check_canonical(value: &Value) -> Result<(), JsonSignatureError> {
+    match value {
+        Value::Number(number) => match number.as_i64() {
+            Some(n) if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&n) => Ok(()),
+            _ => Err(JsonSignatureError::InvalidNumber(number.to_string())),
+        },
+        Value::Array(values) => values.iter().try_for_each(check_canonical),
+        Value::Object(object) => object.values().try_for_each(check_canonical),
+        Value::Null | Value::Bool(_) | Value::String(_) => Ok(()),
+    }
+}
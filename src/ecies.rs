@@ -0,0 +1,185 @@
+use pyo3::{
+    prelude::*,
+    types::{PyBytes, PyType},
+};
+
+use crate::{convert_to_pybytes, error::EciesError, types::Curve25519PublicKey};
+
+/// An unestablished ECIES channel.
+///
+/// This is used to establish the secure channel of the QR code login mechanism
+/// described in MSC4108. Once the channel is established, the `Ecies` object
+/// is consumed and can't be used anymore.
+#[pyclass]
+pub struct Ecies {
+    inner: Option<vodozemac::ecies::Ecies>,
+    public_key: vodozemac::Curve25519PublicKey,
+}
+
+#[pymethods]
+impl Ecies {
+    /// Create a new, random, unestablished ECIES channel.
+    ///
+    /// The optional `info` string is used for domain separation, it defaults
+    /// to the `MATRIX_QR_CODE_LOGIN` info.
+    #[new]
+    #[pyo3(signature = (info = None))]
+    fn new(info: Option<&str>) -> Self {
+        let ecies = match info {
+            Some(info) => vodozemac::ecies::Ecies::with_info(info),
+            None => vodozemac::ecies::Ecies::new(),
+        };
+        let public_key = ecies.public_key();
+
+        Self { inner: Some(ecies), public_key }
+    }
+
+    /// Our public key, it needs to be sent to the other side to establish the
+    /// channel.
+    #[getter]
+    fn public_key(&self) -> Curve25519PublicKey {
+        self.public_key.into()
+    }
+
+    /// Establish the channel using the other side's public key, encrypting the
+    /// given initial plaintext.
+    ///
+    /// Returns the established channel and the initial message which needs to
+    /// be sent to the other side.
+    fn establish_outbound_channel(
+        &mut self,
+        their_public_key: &Curve25519PublicKey,
+        initial_plaintext: &[u8],
+    ) -> Result<(EstablishedEcies, InitialMessage), EciesError> {
+        let ecies = self.inner.take().ok_or(EciesError::Used)?;
+        let result = ecies.establish_outbound_channel(their_public_key.inner, initial_plaintext)?;
+
+        Ok((EstablishedEcies { inner: result.ecies }, InitialMessage { inner: result.message }))
+    }
+
+    /// Establish the channel from an initial message the other side sent us.
+    ///
+    /// Returns the established channel and the decrypted initial plaintext.
+    fn establish_inbound_channel(
+        &mut self,
+        message: &InitialMessage,
+    ) -> Result<(EstablishedEcies, Py<PyBytes>), EciesError> {
+        let ecies = self.inner.take().ok_or(EciesError::Used)?;
+        let result = ecies.establish_inbound_channel(&message.inner)?;
+
+        Ok((EstablishedEcies { inner: result.ecies }, convert_to_pybytes(&result.message)))
+    }
+}
+
+/// An established ECIES channel, used to exchange messages with the other
+/// side.
+#[pyclass]
+pub struct EstablishedEcies {
+    inner: vodozemac::ecies::EstablishedEcies,
+}
+
+#[pymethods]
+impl EstablishedEcies {
+    /// Our public key which was used to establish the channel.
+    #[getter]
+    fn public_key(&self) -> Curve25519PublicKey {
+        self.inner.public_key().into()
+    }
+
+    /// The check code which needs to match on both sides of the channel.
+    #[getter]
+    fn check_code(&self) -> CheckCode {
+        CheckCode { inner: self.inner.check_code().clone() }
+    }
+
+    fn encrypt(&mut self, plaintext: &[u8]) -> EciesMessage {
+        EciesMessage { inner: self.inner.encrypt(plaintext) }
+    }
+
+    fn decrypt(&mut self, message: &EciesMessage) -> Result<Py<PyBytes>, EciesError> {
+        Ok(convert_to_pybytes(&self.inner.decrypt(&message.inner)?))
+    }
+}
+
+/// The code which both sides of an ECIES channel need to compare to ensure
+/// that they share the same secret.
+#[pyclass]
+pub struct CheckCode {
+    inner: vodozemac::ecies::CheckCode,
+}
+
+#[pymethods]
+impl CheckCode {
+    fn as_bytes(&self) -> Py<PyBytes> {
+        convert_to_pybytes(self.inner.as_bytes())
+    }
+
+    /// Convert the check code to a two-digit number.
+    ///
+    /// The number should be displayed with a leading 0 if it is smaller than
+    /// 10.
+    fn to_digit(&self) -> u8 {
+        self.inner.to_digit()
+    }
+
+    #[classattr]
+    const __hash__: Option<Py<PyAny>> = None;
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+/// The initial message which establishes an ECIES channel.
+#[pyclass]
+pub struct InitialMessage {
+    inner: vodozemac::ecies::InitialMessage,
+}
+
+#[pymethods]
+impl InitialMessage {
+    /// Decode an initial message from its string form, the base64-encoded
+    /// ciphertext and public key separated by a `|`.
+    #[classmethod]
+    fn from_base64(_cls: &Bound<'_, PyType>, message: &str) -> Result<Self, EciesError> {
+        Ok(Self { inner: vodozemac::ecies::InitialMessage::decode(message)? })
+    }
+
+    fn to_base64(&self) -> String {
+        self.inner.encode()
+    }
+
+    /// The ephemeral public key of the side that initiated the channel.
+    #[getter]
+    fn public_key(&self) -> Curve25519PublicKey {
+        self.inner.public_key.into()
+    }
+
+    #[getter]
+    fn ciphertext(&self) -> Py<PyBytes> {
+        convert_to_pybytes(&self.inner.ciphertext)
+    }
+}
+
+/// A message sent over an established ECIES channel.
+#[pyclass]
+pub struct EciesMessage {
+    inner: vodozemac::ecies::Message,
+}
+
+#[pymethods]
+impl EciesMessage {
+    #[classmethod]
+    fn from_base64(_cls: &Bound<'_, PyType>, message: &str) -> Result<Self, EciesError> {
+        Ok(Self { inner: vodozemac::ecies::Message::decode(message)? })
+    }
+
+    fn to_base64(&self) -> String {
+        self.inner.encode()
+    }
+
+    #[getter]
+    fn ciphertext(&self) -> Py<PyBytes> {
+        convert_to_pybytes(&self.inner.ciphertext)
+    }
+}
@@ -45,6 +45,7 @@ pyo3::create_exception!(module, SessionCreationException, pyo3::exceptions::PyVa
 pyo3::create_exception!(module, SasException, pyo3::exceptions::PyValueError);
 pyo3::create_exception!(module, OlmDecryptionException, pyo3::exceptions::PyValueError);
 pyo3::create_exception!(module, MegolmDecryptionException, pyo3::exceptions::PyValueError);
+pyo3::create_exception!(module, EciesException, pyo3::exceptions::PyValueError);
 
 #[derive(Debug, Error)]
 pub enum MegolmDecryptionError {
@@ -88,6 +89,46 @@ impl From<SasError> for PyErr {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum SasVerificationError {
+    #[error(transparent)]
+    Json(#[from] JsonSignatureError),
+    #[error("The event doesn't belong to the verification with the transaction ID {0}")]
+    TransactionMismatch(String),
+    #[error("Can't {0} the verification in the {1} state")]
+    InvalidState(&'static str, &'static str),
+}
+
+impl From<SasVerificationError> for PyErr {
+    fn from(e: SasVerificationError) -> Self {
+        match e {
+            SasVerificationError::Json(e) => e.into(),
+            SasVerificationError::TransactionMismatch(_)
+            | SasVerificationError::InvalidState(..) => SasException::new_err(e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EciesError {
+    #[error(transparent)]
+    Ecies(#[from] vodozemac::ecies::Error),
+    #[error(transparent)]
+    Decode(#[from] vodozemac::ecies::MessageDecodeError),
+    #[error("The Ecies object has already been used once.")]
+    Used,
+}
+
+impl From<EciesError> for PyErr {
+    fn from(e: EciesError) -> Self {
+        match e {
+            EciesError::Ecies(e) => EciesException::new_err(e.to_string()),
+            EciesError::Decode(e) => DecodeException::new_err(e.to_string()),
+            EciesError::Used => EciesException::new_err(e.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum SessionError {
     #[error(transparent)]
@@ -100,6 +141,16 @@ pub enum SessionError {
     Utf8(#[from] std::string::FromUtf8Error),
     #[error(transparent)]
     Creation(#[from] vodozemac::olm::SessionCreationError),
+    #[error("None of the Olm sessions with {0} could decrypt the message")]
+    NoMatchingSession(String),
+    #[error("The device {0} is included more than once")]
+    DuplicateRecipient(String),
+    #[error("The session {0} is included more than once")]
+    DuplicateSession(String),
+    #[error(transparent)]
+    Json(#[from] JsonSignatureError),
+    #[error(transparent)]
+    Python(#[from] PyErr),
 }
 
 impl From<SessionError> for PyErr {
@@ -110,6 +161,45 @@ impl From<SessionError> for PyErr {
             SessionError::Decryption(e) => OlmDecryptionException::new_err(e.to_string()),
             SessionError::Creation(e) => SessionCreationException::new_err(e.to_string()),
             SessionError::Utf8(e) => PyValueError::new_err(e.to_string()),
+            SessionError::NoMatchingSession(_) => OlmDecryptionException::new_err(e.to_string()),
+            SessionError::DuplicateRecipient(_) | SessionError::DuplicateSession(_) => {
+                PyValueError::new_err(e.to_string())
+            }
+            SessionError::Json(e) => e.into(),
+            SessionError::Python(e) => e,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JsonSignatureError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Python(#[from] PyErr),
+    #[error("The JSON value isn't an object")]
+    NotAnObject,
+    #[error("The number {0} isn't allowed in canonical JSON")]
+    InvalidNumber(String),
+    #[error("The signatures field of the JSON object isn't valid")]
+    InvalidSignatures,
+    #[error("The JSON object doesn't contain a signature from {0} with the key {1}")]
+    MissingSignature(String, String),
+    #[error(transparent)]
+    Signature(#[from] vodozemac::SignatureError),
+}
+
+impl From<JsonSignatureError> for PyErr {
+    fn from(e: JsonSignatureError) -> Self {
+        match e {
+            JsonSignatureError::Python(e) => e,
+            JsonSignatureError::Json(_)
+            | JsonSignatureError::NotAnObject
+            | JsonSignatureError::InvalidNumber(_)
+            | JsonSignatureError::InvalidSignatures => PyValueError::new_err(e.to_string()),
+            JsonSignatureError::MissingSignature(..) | JsonSignatureError::Signature(_) => {
+                SignatureException::new_err(e.to_string())
+            }
         }
     }
 }
@@ -120,6 +210,8 @@ pub enum PickleError {
     InvalidKeySize(usize),
     #[error(transparent)]
     Unpickling(#[from] vodozemac::PickleError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 }
 
 impl From<PickleError> for PyErr {
@@ -128,6 +220,166 @@ impl From<PickleError> for PyErr {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum DehydratedDeviceError {
+    #[error("The dehydrated device key doesn't have the correct size, got {0}, expected 32 bytes")]
+    InvalidKeySize(usize),
+    #[error(transparent)]
+    Dehydration(#[from] vodozemac::DehydratedDeviceError),
+}
+
+pyo3::create_exception!(module, DehydratedDeviceException, pyo3::exceptions::PyValueError);
+
+impl From<DehydratedDeviceError> for PyErr {
+    fn from(e: DehydratedDeviceError) -> Self {
+        DehydratedDeviceException::new_err(e.to_string())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RecoveryKeyError {
+    #[error(transparent)]
+    Base58(#[from] bs58::decode::Error),
+    #[error("The recovery key doesn't have the correct length, got {0} bytes, expected 35 bytes")]
+    InvalidLength(usize),
+    #[error("The recovery key doesn't start with the 0x8B 0x01 prefix")]
+    InvalidPrefix,
+    #[error("The parity byte of the recovery key doesn't match")]
+    InvalidParity,
+}
+
+pyo3::create_exception!(module, RecoveryKeyException, pyo3::exceptions::PyValueError);
+
+impl From<RecoveryKeyError> for PyErr {
+    fn from(e: RecoveryKeyError) -> Self {
+        RecoveryKeyException::new_err(e.to_string())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SecretStorageError {
+    #[error("The encrypted secret or key description isn't a valid JSON object: {0}")]
+    InvalidJson(String),
+    #[error("The encrypted secret is missing the {0} field")]
+    MissingField(&'static str),
+    #[error(transparent)]
+    Base64(#[from] vodozemac::Base64DecodeError),
+    #[error("The IV doesn't have the correct size, got {0} bytes, expected 16 bytes")]
+    InvalidIvLength(usize),
+    #[error(transparent)]
+    Rounds(#[from] Pbkdf2RoundsError),
+    #[error("The MAC of the encrypted secret doesn't match")]
+    InvalidMac,
+    #[error(transparent)]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error(transparent)]
+    Python(#[from] PyErr),
+}
+
+impl From<JsonSignatureError> for SecretStorageError {
+    fn from(e: JsonSignatureError) -> Self {
+        match e {
+            JsonSignatureError::Python(e) => Self::Python(e),
+            _ => Self::InvalidJson(e.to_string()),
+        }
+    }
+}
+
+pyo3::create_exception!(module, SecretStorageException, pyo3::exceptions::PyValueError);
+
+impl From<SecretStorageError> for PyErr {
+    fn from(e: SecretStorageError) -> Self {
+        match e {
+            SecretStorageError::Python(e) => e,
+            _ => SecretStorageException::new_err(e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AttachmentError {
+    #[error("The encrypted file isn't a valid JSON object: {0}")]
+    InvalidJson(String),
+    #[error("The encrypted file is missing the {0} field")]
+    MissingField(&'static str),
+    #[error("Unsupported encrypted file version {0}")]
+    UnsupportedVersion(String),
+    #[error("Unsupported encrypted file algorithm {0}")]
+    UnsupportedAlgorithm(String),
+    #[error(transparent)]
+    Base64(#[from] vodozemac::Base64DecodeError),
+    #[error("The key doesn't have the correct size, got {0} bytes, expected 32 bytes")]
+    InvalidKeyLength(usize),
+    #[error("The IV doesn't have the correct size, got {0} bytes, expected 16 bytes")]
+    InvalidIvLength(usize),
+    #[error("The SHA-256 hash of the attachment doesn't match")]
+    HashMismatch,
+    #[error("The attachment has already been finalized")]
+    Finalized,
+    #[error(transparent)]
+    Python(#[from] PyErr),
+}
+
+impl From<JsonSignatureError> for AttachmentError {
+    fn from(e: JsonSignatureError) -> Self {
+        match e {
+            JsonSignatureError::Python(e) => Self::Python(e),
+            _ => Self::InvalidJson(e.to_string()),
+        }
+    }
+}
+
+pyo3::create_exception!(module, AttachmentException, pyo3::exceptions::PyValueError);
+pyo3::create_exception!(module, AttachmentHashMismatchException, AttachmentException);
+
+impl From<AttachmentError> for PyErr {
+    fn from(e: AttachmentError) -> Self {
+        match e {
+            AttachmentError::Python(e) => e,
+            AttachmentError::HashMismatch => {
+                AttachmentHashMismatchException::new_err(e.to_string())
+            }
+            _ => AttachmentException::new_err(e.to_string()),
+        }
+    }
+}
+
+/// An error type describing an invalid number of PBKDF2 rounds, which is read
+/// from a key export or a secret storage key description.
+#[derive(Debug, Error)]
+pub enum Pbkdf2RoundsError {
+    #[error("The number of PBKDF2 rounds can't be 0")]
+    Zero,
+    #[error("The number of PBKDF2 rounds can't be more than {1}, got {0}")]
+    TooMany(u32, u32),
+}
+
+#[derive(Debug, Error)]
+pub enum KeyExportError {
+    #[error("The key export is missing the MEGOLM SESSION DATA header or footer")]
+    MissingArmour,
+    #[error(transparent)]
+    Base64(#[from] vodozemac::Base64DecodeError),
+    #[error("The key export is too short, got {0} bytes")]
+    TooShort(usize),
+    #[error("Unsupported key export version {0}")]
+    UnsupportedVersion(u8),
+    #[error(transparent)]
+    Rounds(#[from] Pbkdf2RoundsError),
+    #[error("The MAC of the key export doesn't match, the passphrase might be wrong")]
+    InvalidMac,
+    #[error(transparent)]
+    Utf8(#[from] std::string::FromUtf8Error),
+}
+
+pyo3::create_exception!(module, KeyExportException, pyo3::exceptions::PyValueError);
+
+impl From<KeyExportError> for PyErr {
+    fn from(e: KeyExportError) -> Self {
+        KeyExportException::new_err(e.to_string())
+    }
+}
+
 /// An error type describing failures which can happen during the use of
 /// `PkEncryption` and `PkDecryption` objects.
 #[derive(Debug, Error)]
@@ -138,6 +390,25 @@ pub enum PkEncryptionError {
     Decode(#[from] vodozemac::pk_encryption::Error),
     #[error(transparent)]
     Mac(#[from] vodozemac::Base64DecodeError),
+    #[error("The backed up session data isn't a valid JSON object: {0}")]
+    InvalidJson(String),
+    #[error("The backed up session data is missing the {0} field")]
+    MissingField(&'static str),
+    #[error("Unsupported backed up session algorithm {0}")]
+    UnsupportedAlgorithm(String),
+    #[error(transparent)]
+    SessionKey(#[from] vodozemac::megolm::SessionKeyDecodeError),
+    #[error(transparent)]
+    Python(#[from] PyErr),
+}
+
+impl From<JsonSignatureError> for PkEncryptionError {
+    fn from(e: JsonSignatureError) -> Self {
+        match e {
+            JsonSignatureError::Python(e) => Self::Python(e),
+            _ => Self::InvalidJson(e.to_string()),
+        }
+    }
 }
 
 pyo3::create_exception!(module, PkInvalidKeySizeException, pyo3::exceptions::PyValueError);
@@ -150,7 +421,46 @@ impl From<PkEncryptionError> for PyErr {
                 PkInvalidKeySizeException::new_err(e.to_string())
             }
             PkEncryptionError::Decode(_) => PkDecodeException::new_err(e.to_string()),
-            PkEncryptionError::Mac(_) => PkDecodeException::new_err(e.to_string()),
+            PkEncryptionError::Mac(_)
+            | PkEncryptionError::InvalidJson(_)
+            | PkEncryptionError::MissingField(_)
+            | PkEncryptionError::UnsupportedAlgorithm(_) => {
+                PkDecodeException::new_err(e.to_string())
+            }
+            PkEncryptionError::Python(e) => e,
+            PkEncryptionError::SessionKey(e) => SessionKeyDecodeException::new_err(e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EncryptedContentError {
+    #[error(transparent)]
+    Json(#[from] JsonSignatureError),
+    #[error("The encrypted event content is missing the {0} field")]
+    MissingField(&'static str),
+    #[error("Unsupported encryption algorithm {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("Invalid ciphertext entry for {0}")]
+    InvalidCiphertext(String),
+    #[error(transparent)]
+    Key(#[from] vodozemac::KeyError),
+    #[error(transparent)]
+    Base64(#[from] vodozemac::Base64DecodeError),
+    #[error(transparent)]
+    Decode(#[from] vodozemac::DecodeError),
+    #[error(transparent)]
+    Python(#[from] PyErr),
+}
+
+pyo3::create_exception!(module, EncryptedContentException, pyo3::exceptions::PyValueError);
+
+impl From<EncryptedContentError> for PyErr {
+    fn from(e: EncryptedContentError) -> Self {
+        match e {
+            EncryptedContentError::Json(e) => e.into(),
+            EncryptedContentError::Python(e) => e,
+            _ => EncryptedContentException::new_err(e.to_string()),
         }
     }
 }
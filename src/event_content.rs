@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::prelude::*;
+use serde_json::{json, Map, Value};
+use vodozemac::olm::OlmMessage;
+
+use crate::{
+    canonical_json::JsonObject,
+    error::EncryptedContentError,
+    types::{AnyOlmMessage, Curve25519PublicKey, MegolmMessage},
+};
+
+pub(crate) const OLM_ALGORITHM: &str = "m.olm.v1.curve25519-aes-sha2";
+const MEGOLM_ALGORITHM: &str = "m.megolm.v1.aes-sha2";
+
+/// The content of an `m.room.encrypted` event using the
+/// `m.olm.v1.curve25519-aes-sha2` [algorithm], containing an Olm message for
+/// each recipient device.
+///
+/// [algorithm]: https://spec.matrix.org/v1.11/client-server-api/#mroomencrypted
+#[pyclass]
+pub struct OlmEncryptedContent {
+    sender_key: vodozemac::Curve25519PublicKey,
+    ciphertext: BTreeMap<String, OlmMessage>,
+}
+
+#[pymethods]
+impl OlmEncryptedContent {
+    /// Create the event content from the messages, keyed by the base64-encoded
+    /// Curve25519 identity key of the recipient device, which were returned by
+    /// `Session.encrypt()`.
+    #[new]
+    fn new(
+        sender_key: &Curve25519PublicKey,
+        ciphertext: HashMap<String, PyRef<'_, AnyOlmMessage>>,
+    ) -> Result<Self, EncryptedContentError> {
+        let ciphertext = ciphertext
+            .into_iter()
+            .map(|(k, m)| Ok((recipient_key(&k)?, m.inner.clone())))
+            .collect::<Result<_, EncryptedContentError>>()?;
+
+        Ok(Self { sender_key: sender_key.inner, ciphertext })
+    }
+
+    #[getter]
+    fn algorithm(&self) -> &'static str {
+        OLM_ALGORITHM
+    }
+
+    #[getter]
+    fn sender_key(&self) -> Curve25519PublicKey {
+        self.sender_key.into()
+    }
+
+    /// The messages, keyed by the identity key of the recipient device.
+    #[getter]
+    fn ciphertext(&self) -> HashMap<String, AnyOlmMessage> {
+        self.ciphertext
+            .iter()
+            .map(|(k, m)| (k.clone(), AnyOlmMessage { inner: m.clone() }))
+            .collect()
+    }
+
+    /// Get the message for the device with the given identity key.
+    fn message_for(&self, identity_key: &Curve25519PublicKey) -> Option<AnyOlmMessage> {
+        self.ciphertext
+            .get(&identity_key.inner.to_base64())
+            .map(|m| AnyOlmMessage { inner: m.clone() })
+    }
+
+    /// Convert the content into a dict, ready to be sent.
+    fn to_dict<'py>(&self, py: Python<'py>) -> Result<Bound<'py, PyAny>, EncryptedContentError> {
+        let ciphertext: Map<String, Value> =
+            self.ciphertext.iter().map(|(k, m)| (k.clone(), olm_message_to_json(m))).collect();
+
+        let content = json!({
+            "algorithm": OLM_ALGORITHM,
+            "sender_key": self.sender_key.to_base64(),
+            "ciphertext": ciphertext,
+        });
+
+        into_py(py, content)
+    }
+}
+
+/// The content of an `m.room.encrypted` event using the
+/// `m.megolm.v1.aes-sha2` [algorithm].
+///
+/// The `sender_key` and `device_id` fields are deprecated and optional.
+///
+/// [algorithm]: https://spec.matrix.org/v1.11/client-server-api/#mroomencrypted
+#[pyclass]
+pub struct MegolmEncryptedContent {
+    ciphertext: vodozemac::megolm::MegolmMessage,
+    session_id: String,
+    sender_key: Option<vodozemac::Curve25519PublicKey>,
+    device_id: Option<String>,
+}
+
+#[pymethods]
+impl MegolmEncryptedContent {
+    /// Create the event content from a message returned by
+    /// `GroupSession.encrypt()`.
+    #[new]
+    #[pyo3(signature = (ciphertext, session_id, sender_key = None, device_id = None))]
+    fn new(
+        ciphertext: &MegolmMessage,
+        session_id: &str,
+        sender_key: Option<&Curve25519PublicKey>,
+        device_id: Option<String>,
+    ) -> Self {
+        Self {
+            ciphertext: ciphertext.inner.clone(),
+            session_id: session_id.to_owned(),
+            sender_key: sender_key.map(|k| k.inner),
+            device_id,
+        }
+    }
+
+    #[getter]
+    fn algorithm(&self) -> &'static str {
+        MEGOLM_ALGORITHM
+    }
+
+    #[getter]
+    fn ciphertext(&self) -> MegolmMessage {
+        self.ciphertext.clone().into()
+    }
+
+    #[getter]
+    fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    #[getter]
+    fn sender_key(&self) -> Option<Curve25519PublicKey> {
+        self.sender_key.map(Into::into)
+    }
+
+    #[getter]
+    fn device_id(&self) -> Option<&str> {
+        self.device_id.as_deref()
+    }
+
+    /// Convert the content into a dict, ready to be sent.
+    fn to_dict<'py>(&self, py: Python<'py>) -> Result<Bound<'py, PyAny>, EncryptedContentError> {
+        let mut content = json!({
+            "algorithm": MEGOLM_ALGORITHM,
+            "ciphertext": self.ciphertext.to_base64(),
+            "session_id": self.session_id,
+        });
+
+        if let Some(sender_key) = &self.sender_key {
+            content["sender_key"] = Value::String(sender_key.to_base64());
+        }
+
+        if let Some(device_id) = &self.device_id {
+            content["device_id"] = Value::String(device_id.clone());
+        }
+
+        into_py(py, content)
+    }
+}
+
+/// Parse the content of an `m.room.encrypted` event, given as a dict or a JSON
+/// string.
+///
+/// Returns an `OlmEncryptedContent` or a `MegolmEncryptedContent`, depending
+/// on the `algorithm` of the content.
+#[pyfunction]
+pub fn parse_encrypted_content<'py>(
+    content: &Bound<'py, PyAny>,
+) -> Result<Bound<'py, PyAny>, EncryptedContentError> {
+    let py = content.py();
+    let content = JsonObject::extract(content)?.inner;
+
+    match get_str(&content, "algorithm")? {
+        OLM_ALGORITHM => {
+            let sender_key =
+                vodozemac::Curve25519PublicKey::from_base64(get_str(&content, "sender_key")?)?;
+
+            let ciphertext = content
+                .get("ciphertext")
+                .and_then(Value::as_object)
+                .ok_or(EncryptedContentError::MissingField("ciphertext"))?
+                .iter()
+                .map(|(key, message)| {
+                    let message = message
+                        .as_object()
+                        .ok_or_else(|| EncryptedContentError::InvalidCiphertext(key.clone()))?;
+                    let message_type = message
+                        .get("type")
+                        .and_then(Value::as_u64)
+                        .ok_or(EncryptedContentError::MissingField("type"))?;
+                    let body = vodozemac::base64_decode(get_str(message, "body")?)?;
+
+                    let message = OlmMessage::from_parts(message_type as usize, &body)?;
+
+                    Ok((recipient_key(key)?, message))
+                })
+                .collect::<Result<_, EncryptedContentError>>()?;
+
+            Ok(Bound::new(py, OlmEncryptedContent { sender_key, ciphertext })?.into_any())
+        }
+        MEGOLM_ALGORITHM => {
+            let ciphertext =
+                vodozemac::megolm::MegolmMessage::from_base64(get_str(&content, "ciphertext")?)?;
+            let session_id = get_str(&content, "session_id")?.to_owned();
+            let sender_key = content
+                .get("sender_key")
+                .and_then(Value::as_str)
+                .map(vodozemac::Curve25519PublicKey::from_base64)
+                .transpose()?;
+            let device_id = content.get("device_id").and_then(Value::as_str).map(str::to_owned);
+
+            Ok(Bound::new(
+                py,
+                MegolmEncryptedContent { ciphertext, session_id, sender_key, device_id },
+            )?
+            .into_any())
+        }
+        algorithm => Err(EncryptedContentError::UnsupportedAlgorithm(algorithm.to_owned())),
+    }
+}
+
+/// Convert an Olm message into the `type` and `body` object of the
+/// `ciphertext` map.
+pub(crate) fn olm_message_to_json(message: &OlmMessage) -> Value {
+    let (message_type, body) = message.to_parts();
+
+    json!({
+        "type": message_type,
+        "body": vodozemac::base64_encode(body),
+    })
+}
+
+/// Check that the key of a `ciphertext` entry is a valid Curve25519 key,
+/// returning it in its canonical base64 form.
+fn recipient_key(key: &str) -> Result<String, EncryptedContentError> {
+    Ok(vodozemac::Curve25519PublicKey::from_base64(key)?.to_base64())
+}
+
+fn into_py(py: Python<'_>, content: Value) -> Result<Bound<'_, PyAny>, EncryptedContentError> {
+    let Value::Object(content) = content else {
+        unreachable!("the event content is always a JSON object");
+    };
+
+    Ok(JsonObject::new(content).into_py(py)?)
+}
+
+fn get_str<'a>(
+    object: &'a Map<String, Value>,
+    field: &'static str,
+) -> Result<&'a str, EncryptedContentError> {
+    object.get(field).and_then(Value::as_str).ok_or(EncryptedContentError::MissingField(field))
+}
@@ -7,9 +7,90 @@ use vodozemac::megolm::SessionConfig;
 use crate::{
     convert_to_pybytes,
     error::{LibolmPickleError, MegolmDecryptionError, PickleError, SessionKeyDecodeError},
-    types::{ExportedSessionKey, MegolmMessage, SessionKey},
+    types::{Ed25519PublicKey, ExportedSessionKey, MegolmMessage, SessionKey},
 };
 
+/// The configuration for a Megolm `GroupSession` or `InboundGroupSession`.
+///
+/// Version 1 truncates the MAC of each message to 8 bytes, version 2 uses the
+/// full-length MAC.
+#[pyclass(from_py_object)]
+#[derive(Clone)]
+pub struct MegolmSessionConfig {
+    pub(crate) inner: SessionConfig,
+}
+
+#[pymethods]
+impl MegolmSessionConfig {
+    /// Create a config for the Megolm version 1, using a truncated MAC.
+    #[classmethod]
+    fn version_1(_cls: &Bound<'_, PyType>) -> Self {
+        Self { inner: SessionConfig::version_1() }
+    }
+
+    /// Create a config for the Megolm version 2, using a full-length MAC.
+    #[classmethod]
+    fn version_2(_cls: &Bound<'_, PyType>) -> Self {
+        Self { inner: SessionConfig::version_2() }
+    }
+
+    /// The numeric version of this config.
+    #[getter]
+    fn version(&self) -> u8 {
+        self.inner.version()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MegolmSessionConfig(version={})", self.inner.version())
+    }
+
+    #[classattr]
+    const __hash__: Option<Py<PyAny>> = None;
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl From<SessionConfig> for MegolmSessionConfig {
+    fn from(value: SessionConfig) -> Self {
+        Self { inner: value }
+    }
+}
+
+pub(crate) fn session_config_or_default(
+    session_config: Option<MegolmSessionConfig>,
+) -> SessionConfig {
+    session_config.map(|c| c.inner).unwrap_or_else(SessionConfig::version_1)
+}
+
+/// How an `InboundGroupSession` relates to another session with the same
+/// session ID, e.g. one received again via a forward, a backup or an export.
+#[pyclass(eq, eq_int, from_py_object)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SessionOrdering {
+    /// The sessions are the same.
+    Equal,
+    /// The session can decrypt more messages than the other one.
+    Better,
+    /// The session can decrypt fewer messages than the other one.
+    Worse,
+    /// The sessions aren't connected, i.e. they weren't created from the same
+    /// `GroupSession`.
+    Unconnected,
+}
+
+impl From<vodozemac::megolm::SessionOrdering> for SessionOrdering {
+    fn from(value: vodozemac::megolm::SessionOrdering) -> Self {
+        match value {
+            vodozemac::megolm::SessionOrdering::Equal => Self::Equal,
+            vodozemac::megolm::SessionOrdering::Better => Self::Better,
+            vodozemac::megolm::SessionOrdering::Worse => Self::Worse,
+            vodozemac::megolm::SessionOrdering::Unconnected => Self::Unconnected,
+        }
+    }
+}
+
 #[pyclass]
 pub struct GroupSession {
     pub(super) inner: vodozemac::megolm::GroupSession,
@@ -18,8 +99,11 @@ pub struct GroupSession {
 #[pymethods]
 impl GroupSession {
     #[new]
-    fn new() -> Self {
-        Self { inner: vodozemac::megolm::GroupSession::new(SessionConfig::version_1()) }
+    #[pyo3(signature = (session_config = None))]
+    fn new(session_config: Option<MegolmSessionConfig>) -> Self {
+        Self {
+            inner: vodozemac::megolm::GroupSession::new(session_config_or_default(session_config)),
+        }
     }
 
     #[getter]
@@ -27,6 +111,11 @@ impl GroupSession {
         self.inner.session_id()
     }
 
+    #[getter]
+    fn session_config(&self) -> MegolmSessionConfig {
+        self.inner.session_config().into()
+    }
+
     #[getter]
     fn message_index(&self) -> u32 {
         self.inner.message_index()
@@ -81,31 +170,32 @@ impl DecryptedMessage {
 #[pyclass]
 pub struct InboundGroupSession {
     pub(super) inner: vodozemac::megolm::InboundGroupSession,
+    session_config: Option<SessionConfig>,
+    highest_decrypted_index: Option<u32>,
 }
 
 #[pymethods]
 impl InboundGroupSession {
     #[new]
-    fn new(session_key: &SessionKey) -> Result<Self, SessionKeyDecodeError> {
-        Ok(Self {
-            inner: vodozemac::megolm::InboundGroupSession::new(
-                &session_key.inner,
-                SessionConfig::version_1(),
-            ),
-        })
+    #[pyo3(signature = (session_key, session_config = None))]
+    fn new(
+        session_key: &SessionKey,
+        session_config: Option<MegolmSessionConfig>,
+    ) -> Result<Self, SessionKeyDecodeError> {
+        let session_config = session_config_or_default(session_config);
+        let inner = vodozemac::megolm::InboundGroupSession::new(&session_key.inner, session_config);
+
+        Ok(Self { session_config: Some(session_config), ..inner.into() })
     }
 
     #[classmethod]
+    #[pyo3(signature = (session_key, session_config = None))]
     fn import_session(
         _cls: &Bound<'_, PyType>,
         session_key: &ExportedSessionKey,
+        session_config: Option<MegolmSessionConfig>,
     ) -> Result<Self, SessionKeyDecodeError> {
-        Ok(Self {
-            inner: vodozemac::megolm::InboundGroupSession::import(
-                &session_key.inner,
-                SessionConfig::version_1(),
-            ),
-        })
+        Ok(Self::import(&session_key.inner, session_config_or_default(session_config)))
     }
 
     #[getter]
@@ -113,20 +203,99 @@ impl InboundGroupSession {
         self.inner.session_id()
     }
 
+    /// The Ed25519 key which signs the messages of this session.
+    #[getter]
+    fn signing_key(&self) -> Ed25519PublicKey {
+        // The session ID is the base64-encoded signing key.
+        vodozemac::Ed25519PublicKey::from_base64(&self.inner.session_id())
+            .expect("the session ID is always a valid Ed25519 public key")
+            .into()
+    }
+
+    /// The config this session was created with.
+    ///
+    /// This is `None` if the session was restored from a pickle, since
+    /// vodozemac doesn't expose the config of a pickled session.
+    #[getter]
+    fn session_config(&self) -> Option<MegolmSessionConfig> {
+        self.session_config.map(Into::into)
+    }
+
     #[getter]
     fn first_known_index(&self) -> u32 {
         self.inner.first_known_index()
     }
 
+    /// The highest message index which was successfully decrypted with this
+    /// session, or `None` if no message was decrypted yet.
+    ///
+    /// The index isn't part of the pickle, it needs to be stored next to it
+    /// and passed to `from_pickle()`.
+    #[getter]
+    fn highest_decrypted_index(&self) -> Option<u32> {
+        self.highest_decrypted_index
+    }
+
+    /// Export the session at the given message index.
+    ///
+    /// This doesn't discard any message keys, the session can still decrypt
+    /// messages below the index afterwards, use `advance_to()` for that.
     fn export_at(&mut self, index: u32) -> Option<ExportedSessionKey> {
         self.inner.export_at(index).map(|k| k.into())
     }
 
+    /// Permanently advance the session to the given message index, discarding
+    /// the message keys of all the earlier messages.
+    ///
+    /// Returns `False` if the session was already at or beyond the index.
+    fn advance_to(&mut self, index: u32) -> bool {
+        self.inner.advance_to(index)
+    }
+
+    /// Check if the session can still decrypt the message with the given
+    /// index.
+    fn has_ratchet_at(&self, index: u32) -> bool {
+        index >= self.inner.first_known_index()
+    }
+
+    /// Compare this session with another copy of it, to decide which one
+    /// should be kept.
+    fn compare(slf: &Bound<'_, Self>, other: &Bound<'_, Self>) -> SessionOrdering {
+        Self::with_other(slf, other, |session, other| session.compare(other)).into()
+    }
+
+    /// Check if the two sessions were created from the same `GroupSession`,
+    /// possibly at different message indices.
+    fn connected(slf: &Bound<'_, Self>, other: &Bound<'_, Self>) -> bool {
+        Self::with_other(slf, other, |session, other| session.connected(other))
+    }
+
+    /// Merge the two sessions into a new one, which uses the lower first known
+    /// index of the two and is verified if either of them is.
+    ///
+    /// Returns `None` if the sessions aren't connected.
+    fn merge(slf: &Bound<'_, Self>, other: &Bound<'_, Self>) -> Option<Self> {
+        let (session_config, highest_decrypted_index) = {
+            let (session, other) = (slf.borrow(), other.borrow());
+            (
+                session.session_config.or(other.session_config),
+                session.highest_decrypted_index.max(other.highest_decrypted_index),
+            )
+        };
+
+        Self::with_other(slf, other, |session, other| session.merge(other)).map(|inner| Self {
+            session_config,
+            highest_decrypted_index,
+            ..inner.into()
+        })
+    }
+
     fn decrypt(
         &mut self,
         message: &MegolmMessage,
     ) -> Result<DecryptedMessage, MegolmDecryptionError> {
         let ret = self.inner.decrypt(&message.inner)?;
+        self.highest_decrypted_index = self.highest_decrypted_index.max(Some(ret.message_index));
 
         Ok(DecryptedMessage::new(ret.plaintext.as_slice(), ret.message_index))
     }
@@ -138,20 +307,25 @@ impl InboundGroupSession {
         Ok(self.inner.pickle().encrypt(pickle_key))
     }
 
+    /// Restore a session from a pickle, along with the `highest_decrypted_index`
+    /// which was stored next to it.
     #[classmethod]
+    #[pyo3(signature = (pickle, pickle_key, highest_decrypted_index = None))]
     fn from_pickle(
         _cls: &Bound<'_, PyType>,
         pickle: &str,
         pickle_key: &[u8],
+        highest_decrypted_index: Option<u32>,
     ) -> Result<Self, PickleError> {
         let pickle_key: &[u8; 32] =
             pickle_key.try_into().map_err(|_| PickleError::InvalidKeySize(pickle_key.len()))?;
         let pickle =
             vodozemac::megolm::InboundGroupSessionPickle::from_encrypted(pickle, pickle_key)?;
 
-        let session = vodozemac::megolm::InboundGroupSession::from_pickle(pickle);
-
-        Ok(Self { inner: session })
+        Ok(Self {
+            highest_decrypted_index,
+            ..vodozemac::megolm::InboundGroupSession::from_pickle(pickle).into()
+        })
     }
 
     #[classmethod]
@@ -162,6 +336,49 @@ impl InboundGroupSession {
     ) -> Result<Self, LibolmPickleError> {
         let inner = vodozemac::megolm::InboundGroupSession::from_libolm_pickle(pickle, pickle_key)?;
 
-        Ok(Self { inner })
+        // libolm only supports the version 1 config.
+        Ok(Self { session_config: Some(SessionConfig::version_1()), ..inner.into() })
+    }
+}
+
+impl From<vodozemac::megolm::InboundGroupSession> for InboundGroupSession {
+    fn from(value: vodozemac::megolm::InboundGroupSession) -> Self {
+        Self { inner: value, session_config: None, highest_decrypted_index: None }
+    }
+}
+
+impl InboundGroupSession {
+    /// Import a session from an exported session key, using the given config.
+    pub(crate) fn import(
+        session_key: &vodozemac::megolm::ExportedSessionKey,
+        session_config: SessionConfig,
+    ) -> Self {
+        let inner = vodozemac::megolm::InboundGroupSession::import(session_key, session_config);
+
+        Self { session_config: Some(session_config), ..inner.into() }
+    }
+
+    /// Call `f` with mutable access to both sessions, vodozemac needs it to
+    /// advance the ratchets while comparing them.
+    ///
+    /// If both are the same Python object, a copy of the session is used as
+    /// the other session, since it can't be mutably borrowed twice.
+    fn with_other<T>(
+        slf: &Bound<'_, Self>,
+        other: &Bound<'_, Self>,
+        f: impl FnOnce(
+            &mut vodozemac::megolm::InboundGroupSession,
+            &mut vodozemac::megolm::InboundGroupSession,
+        ) -> T,
+    ) -> T {
+        let mut session = slf.borrow_mut();
+
+        if slf.is(other) {
+            let mut copy =
+                vodozemac::megolm::InboundGroupSession::from_pickle(session.inner.pickle());
+            f(&mut session.inner, &mut copy)
+        } else {
+            f(&mut session.inner, &mut other.borrow_mut().inner)
+        }
     }
 }
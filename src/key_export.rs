@@ -0,0 +1,171 @@
+use aes::{
+    cipher::{KeyIvInit, StreamCipher},
+    Aes256,
+};
+use hmac::{Hmac, Mac};
+use pyo3::prelude::*;
+use rand::{thread_rng, RngCore};
+use sha2::{Sha256, Sha512};
+
+use crate::error::{KeyExportError, Pbkdf2RoundsError};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+const HEADER: &str = "-----BEGIN MEGOLM SESSION DATA-----";
+const FOOTER: &str = "-----END MEGOLM SESSION DATA-----";
+
+const VERSION: u8 = 1;
+const SALT_SIZE: usize = 16;
+const IV_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+const MAC_SIZE: usize = 32;
+const HEADER_SIZE: usize = 1 + SALT_SIZE + IV_SIZE + 4;
+
+/// The maximum number of PBKDF2 rounds, ten times the default. The rounds of an
+/// export or a secret storage key are read from untrusted input, a higher
+/// number would let a crafted one block the caller for hours.
+const MAX_ROUNDS: u32 = 5_000_000;
+
+/// The number of characters per line of the base64-encoded payload, the same
+/// as Element uses.
+const LINE_LENGTH: usize = 96;
+
+/// Encrypt a list of exported room keys into the Matrix [key export] format.
+///
+/// The `json` string is the JSON-encoded list of exported room keys, it is
+/// encrypted using a key derived from the passphrase using the given number of
+/// PBKDF2 rounds.
+///
+/// [key export]: https://spec.matrix.org/v1.11/client-server-api/#key-export-format
+#[pyfunction]
+#[pyo3(signature = (json, passphrase, rounds = 500_000))]
+pub fn encrypt_key_export(
+    py: Python<'_>,
+    json: &str,
+    passphrase: &str,
+    rounds: u32,
+) -> Result<String, KeyExportError> {
+    check_rounds(rounds)?;
+
+    let mut salt = [0u8; SALT_SIZE];
+    let mut iv = [0u8; IV_SIZE];
+
+    let mut rng = thread_rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut iv);
+
+    // Clear bit 63 of the counter, so the counter doesn't overflow when
+    // implementations only use the lower 64 bits of the IV as the counter.
+    iv[8] &= 0x7f;
+
+    let (aes_key, mac_key) = py.detach(|| derive_keys(passphrase, &salt, rounds));
+
+    let mut ciphertext = json.as_bytes().to_vec();
+    Aes256Ctr::new(&aes_key.into(), &iv.into()).apply_keystream(&mut ciphertext);
+
+    let mut payload = Vec::with_capacity(HEADER_SIZE + ciphertext.len() + MAC_SIZE);
+    payload.push(VERSION);
+    payload.extend_from_slice(&salt);
+    payload.extend_from_slice(&iv);
+    payload.extend_from_slice(&rounds.to_be_bytes());
+    payload.extend_from_slice(&ciphertext);
+
+    let mut mac = HmacSha256::new_from_slice(&mac_key).expect("HMAC can take a key of any size");
+    mac.update(&payload);
+    payload.extend_from_slice(&mac.finalize().into_bytes());
+
+    let mut encoded = vodozemac::base64_encode(&payload);
+    encoded.extend(std::iter::repeat_n('=', (4 - encoded.len() % 4) % 4));
+
+    let mut export = String::from(HEADER);
+
+    for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+        export.push('\n');
+        export.push_str(std::str::from_utf8(line).expect("base64 is always valid UTF-8"));
+    }
+
+    export.push('\n');
+    export.push_str(FOOTER);
+    export.push('\n');
+
+    Ok(export)
+}
+
+/// Decrypt a file in the Matrix [key export] format, returning the
+/// JSON-encoded list of exported room keys.
+///
+/// [key export]: https://spec.matrix.org/v1.11/client-server-api/#key-export-format
+#[pyfunction]
+pub fn decrypt_key_export(
+    py: Python<'_>,
+    data: &str,
+    passphrase: &str,
+) -> Result<String, KeyExportError> {
+    let data = data.trim();
+    let encoded = data
+        .strip_prefix(HEADER)
+        .and_then(|data| data.strip_suffix(FOOTER))
+        .ok_or(KeyExportError::MissingArmour)?;
+
+    let encoded: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
+    let payload = vodozemac::base64_decode(encoded)?;
+
+    if payload.len() < HEADER_SIZE + MAC_SIZE {
+        return Err(KeyExportError::TooShort(payload.len()));
+    }
+
+    if payload[0] != VERSION {
+        return Err(KeyExportError::UnsupportedVersion(payload[0]));
+    }
+
+    let (payload, expected_mac) = payload.split_at(payload.len() - MAC_SIZE);
+    let (header, ciphertext) = payload.split_at(HEADER_SIZE);
+
+    let salt = &header[1..1 + SALT_SIZE];
+    let iv: [u8; IV_SIZE] = header[1 + SALT_SIZE..1 + SALT_SIZE + IV_SIZE]
+        .try_into()
+        .expect("the header contains a 16 byte IV");
+    let rounds = u32::from_be_bytes(
+        header[1 + SALT_SIZE + IV_SIZE..]
+            .try_into()
+            .expect("the header contains 4 bytes of rounds"),
+    );
+
+    check_rounds(rounds)?;
+
+    let (aes_key, mac_key) = py.detach(|| derive_keys(passphrase, salt, rounds));
+
+    let mut mac = HmacSha256::new_from_slice(&mac_key).expect("HMAC can take a key of any size");
+    mac.update(payload);
+    mac.verify_slice(expected_mac).map_err(|_| KeyExportError::InvalidMac)?;
+
+    let mut plaintext = ciphertext.to_vec();
+    Aes256Ctr::new(&aes_key.into(), &iv.into()).apply_keystream(&mut plaintext);
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+pub(crate) fn check_rounds(rounds: u32) -> Result<(), Pbkdf2RoundsError> {
+    if rounds == 0 {
+        Err(Pbkdf2RoundsError::Zero)
+    } else if rounds > MAX_ROUNDS {
+        Err(Pbkdf2RoundsError::TooMany(rounds, MAX_ROUNDS))
+    } else {
+        Ok(())
+    }
+}
+
+/// Derive the AES and the HMAC key from the passphrase using
+/// PBKDF2-HMAC-SHA512.
+fn derive_keys(passphrase: &str, salt: &[u8], rounds: u32) -> ([u8; KEY_SIZE], [u8; KEY_SIZE]) {
+    let mut keys = [0u8; KEY_SIZE * 2];
+    pbkdf2::pbkdf2_hmac::<Sha512>(passphrase.as_bytes(), salt, rounds, &mut keys);
+
+    let mut aes_key = [0u8; KEY_SIZE];
+    let mut mac_key = [0u8; KEY_SIZE];
+    aes_key.copy_from_slice(&keys[..KEY_SIZE]);
+    mac_key.copy_from_slice(&keys[KEY_SIZE..]);
+
+    (aes_key, mac_key)
+}
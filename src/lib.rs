@@ -1,10 +1,19 @@
 mod account;
+mod attachments;
+mod canonical_json;
+mod ecies;
 mod error;
+mod event_content;
 mod group_sessions;
+mod key_export;
 mod pk_encryption;
+mod recovery_key;
 mod sas;
+mod secret_storage;
 mod session;
+mod session_store;
 mod types;
+mod verification;
 
 use error::*;
 use pyo3::{prelude::*, types::PyBytes};
@@ -17,34 +26,70 @@ use pyo3::{prelude::*, types::PyBytes};
 /// - Olm (end-to-end encryption for 1:1 conversations)
 /// - Megolm (end-to-end encryption for group conversations)
 /// - SAS (Short Authentication String) verification
+/// - ECIES secure channels for QR code login
 /// - Public key encryption (PK encryption) for key backup
+/// - Encrypted room key exports
+/// - Secret storage (SSSS) encryption
+/// - Encrypted attachments
+/// - Parsing and building encrypted event content
 ///
 /// All the classes and functions in this module are thread-safe and can be used
 /// in concurrent environments.
 fn my_module(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<account::Account>()?;
+    m.add_class::<account::OneTimeKeyGenerationResult>()?;
+    m.add_class::<account::InboundCreationResult>()?;
     m.add_class::<session::Session>()?;
+    m.add_class::<session::OlmSessionConfig>()?;
+    m.add_class::<session_store::OlmSessionStore>()?;
+    m.add_class::<session_store::OlmDecryptionResult>()?;
+    m.add_class::<types::SessionKeys>()?;
     m.add_class::<types::AnyOlmMessage>()?;
     m.add_class::<types::PreKeyMessage>()?;
+    m.add_class::<types::NormalOlmMessage>()?;
 
     m.add_class::<sas::Sas>()?;
     m.add_class::<sas::EstablishedSas>()?;
+    m.add_class::<verification::SasVerification>()?;
+
+    m.add_class::<ecies::Ecies>()?;
+    m.add_class::<ecies::EstablishedEcies>()?;
+    m.add_class::<ecies::InitialMessage>()?;
+    m.add_class::<ecies::EciesMessage>()?;
+    m.add_class::<ecies::CheckCode>()?;
 
     m.add_class::<group_sessions::GroupSession>()?;
     m.add_class::<group_sessions::InboundGroupSession>()?;
+    m.add_class::<group_sessions::MegolmSessionConfig>()?;
+    m.add_class::<group_sessions::SessionOrdering>()?;
     m.add_class::<types::SessionKey>()?;
     m.add_class::<types::ExportedSessionKey>()?;
     m.add_class::<types::MegolmMessage>()?;
 
     m.add_class::<types::Ed25519PublicKey>()?;
+    m.add_class::<types::Ed25519SecretKey>()?;
     m.add_class::<types::Ed25519Signature>()?;
     m.add_class::<types::Curve25519PublicKey>()?;
     m.add_class::<types::Curve25519SecretKey>()?;
+    m.add_class::<types::SharedSecret>()?;
 
     m.add_class::<pk_encryption::PkDecryption>()?;
     m.add_class::<pk_encryption::PkEncryption>()?;
     m.add_class::<pk_encryption::Message>()?;
 
+    m.add_class::<secret_storage::SecretStorageKey>()?;
+
+    m.add_class::<attachments::AttachmentEncryptor>()?;
+    m.add_class::<attachments::AttachmentDecryptor>()?;
+
+    m.add_class::<event_content::OlmEncryptedContent>()?;
+    m.add_class::<event_content::MegolmEncryptedContent>()?;
+    m.add_function(wrap_pyfunction!(event_content::parse_encrypted_content, m)?)?;
+    m.add_function(wrap_pyfunction!(session::encrypt_for_devices, m)?)?;
+
+    m.add_function(wrap_pyfunction!(key_export::encrypt_key_export, m)?)?;
+    m.add_function(wrap_pyfunction!(key_export::decrypt_key_export, m)?)?;
+
     m.add("KeyException", py.get_type::<KeyException>())?;
     m.add("SignatureException", py.get_type::<SignatureException>())?;
     m.add("DecodeException", py.get_type::<DecodeException>())?;
@@ -55,8 +100,16 @@ fn my_module(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add("SasException", py.get_type::<SasException>())?;
     m.add("OlmDecryptionException", py.get_type::<OlmDecryptionException>())?;
     m.add("MegolmDecryptionException", py.get_type::<MegolmDecryptionException>())?;
+    m.add("EciesException", py.get_type::<EciesException>())?;
+    m.add("DehydratedDeviceException", py.get_type::<DehydratedDeviceException>())?;
+    m.add("KeyExportException", py.get_type::<KeyExportException>())?;
+    m.add("RecoveryKeyException", py.get_type::<RecoveryKeyException>())?;
+    m.add("SecretStorageException", py.get_type::<SecretStorageException>())?;
+    m.add("AttachmentException", py.get_type::<AttachmentException>())?;
+    m.add("AttachmentHashMismatchException", py.get_type::<AttachmentHashMismatchException>())?;
     m.add("PkInvalidKeySizeException", py.get_type::<PkInvalidKeySizeException>())?;
     m.add("PkDecodeException", py.get_type::<PkDecodeException>())?;
+    m.add("EncryptedContentException", py.get_type::<EncryptedContentException>())?;
 
     Ok(())
 }
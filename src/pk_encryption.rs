@@ -1,14 +1,22 @@
+use std::collections::BTreeMap;
+
 use pyo3::{
     pyclass, pymethods,
     types::{PyBytes, PyType},
-    Bound, Py, Python,
+    Bound, Py, PyAny, Python,
 };
+use serde_json::{json, Map, Value};
 
 use crate::{
+    canonical_json::JsonObject,
+    group_sessions::{session_config_or_default, InboundGroupSession, MegolmSessionConfig},
     types::{Curve25519PublicKey, Curve25519SecretKey},
     PkEncryptionError,
 };
 
+/// The algorithm of the room keys which are stored in the key backup.
+const BACKUP_SESSION_ALGORITHM: &str = "m.megolm.v1.aes-sha2";
+
 /// A message that was encrypted using a PkEncryption object.
 #[pyclass]
 pub struct Message {
@@ -60,15 +68,7 @@ impl Message {
         mac: &str,
         ephemeral_key: &str,
     ) -> Result<Self, PkEncryptionError> {
-        let decoded_ciphertext = vodozemac::base64_decode(ciphertext)?;
-        let decoded_mac = vodozemac::base64_decode(mac)?;
-        let decoded_ephemeral_key = vodozemac::base64_decode(ephemeral_key)?;
-
-        Ok(Self {
-            ciphertext: decoded_ciphertext,
-            mac: decoded_mac,
-            ephemeral_key: decoded_ephemeral_key,
-        })
+        Self::from_base64_parts(ciphertext, mac, ephemeral_key)
     }
 
     /// Convert the message components to unpadded Base64-encoded strings.
@@ -80,7 +80,25 @@ impl Message {
         let mac_b64 = vodozemac::base64_encode(&self.mac);
         let ephemeral_key_b64 = vodozemac::base64_encode(&self.ephemeral_key);
 
-        Ok((ephemeral_key_b64, mac_b64, ciphertext_b64))
+        Ok((ciphertext_b64, mac_b64, ephemeral_key_b64))
+    }
+}
+
+impl Message {
+    fn from_base64_parts(
+        ciphertext: &str,
+        mac: &str,
+        ephemeral_key: &str,
+    ) -> Result<Self, PkEncryptionError> {
+        let decoded_ciphertext = vodozemac::base64_decode(ciphertext)?;
+        let decoded_mac = vodozemac::base64_decode(mac)?;
+        let decoded_ephemeral_key = vodozemac::base64_decode(ephemeral_key)?;
+
+        Ok(Self {
+            ciphertext: decoded_ciphertext,
+            mac: decoded_mac,
+            ephemeral_key: decoded_ephemeral_key,
+        })
     }
 }
 
@@ -131,6 +149,64 @@ impl PkDecryption {
     /// Decrypt a ciphertext. See the PkEncryption::encrypt function
     /// for descriptions of the ephemeral_key and mac arguments.
     pub fn decrypt(&self, message: &Message) -> Result<Py<PyBytes>, PkEncryptionError> {
+        self.decrypt_message(message).map(|vec| {
+            Python::try_attach(|py| PyBytes::new(py, vec.as_slice()).into())
+                .expect("failed to attach to Python interpreter")
+        })
+    }
+
+    /// Decrypt the `session_data` of a room key which was stored in the key
+    /// backup.
+    ///
+    /// Returns the imported `InboundGroupSession` and the decrypted session
+    /// data, which contains the `sender_key`, the `sender_claimed_keys` and
+    /// the `forwarding_curve25519_key_chain` of the room key.
+    #[pyo3(signature = (session_data, session_config = None))]
+    pub fn decrypt_backup_session<'py>(
+        &self,
+        py: Python<'py>,
+        session_data: &Bound<'py, PyAny>,
+        session_config: Option<MegolmSessionConfig>,
+    ) -> Result<(InboundGroupSession, Bound<'py, PyAny>), PkEncryptionError> {
+        let session_data = JsonObject::extract(session_data)?;
+        let field = |name: &'static str| {
+            session_data
+                .inner
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or(PkEncryptionError::MissingField(name))
+        };
+
+        let message =
+            Message::from_base64_parts(field("ciphertext")?, field("mac")?, field("ephemeral")?)?;
+
+        let plaintext = self.decrypt_message(&message)?;
+        let plaintext: Map<String, Value> = serde_json::from_slice(&plaintext)
+            .map_err(|e| PkEncryptionError::InvalidJson(e.to_string()))?;
+
+        let algorithm = plaintext.get("algorithm").and_then(Value::as_str);
+
+        if algorithm != Some(BACKUP_SESSION_ALGORITHM) {
+            return Err(PkEncryptionError::UnsupportedAlgorithm(
+                algorithm.unwrap_or_default().to_owned(),
+            ));
+        }
+
+        let session_key = plaintext
+            .get("session_key")
+            .and_then(Value::as_str)
+            .ok_or(PkEncryptionError::MissingField("session_key"))?;
+        let session_key = vodozemac::megolm::ExportedSessionKey::from_base64(session_key)?;
+
+        let session =
+            InboundGroupSession::import(&session_key, session_config_or_default(session_config));
+
+        Ok((session, JsonObject::new(plaintext).into_py(py)?))
+    }
+}
+
+impl PkDecryption {
+    fn decrypt_message(&self, message: &Message) -> Result<Vec<u8>, PkEncryptionError> {
         let ephemeral_key_bytes: [u8; 32] = message
             .ephemeral_key
             .as_slice()
@@ -143,13 +219,7 @@ impl PkDecryption {
             ephemeral_key: vodozemac::Curve25519PublicKey::from_bytes(ephemeral_key_bytes),
         };
 
-        self.inner
-            .decrypt(&message)
-            .map(|vec| {
-                Python::try_attach(|py| PyBytes::new(py, vec.as_slice()).into())
-                    .expect("failed to attach to Python interpreter")
-            })
-            .map_err(PkEncryptionError::Decode)
+        self.inner.decrypt(&message).map_err(PkEncryptionError::Decode)
     }
 }
 
@@ -193,4 +263,38 @@ impl PkEncryption {
             ephemeral_key: msg.ephemeral_key.to_vec(),
         }
     }
+
+    /// Encrypt a room key for the key backup, returning the `session_data`
+    /// of the backed up room key.
+    ///
+    /// The session is exported at its first known index, the `sender_key` is
+    /// the Curve25519 key of the device which created the session and the
+    /// `sender_claimed_keys` the keys it claimed to own, usually
+    /// `{"ed25519": <key>}`.
+    pub fn encrypt_backup_session<'py>(
+        &self,
+        py: Python<'py>,
+        session: &InboundGroupSession,
+        sender_key: &Curve25519PublicKey,
+        forwarding_chain: Vec<String>,
+        sender_claimed_keys: BTreeMap<String, String>,
+    ) -> Result<Bound<'py, PyAny>, PkEncryptionError> {
+        let plaintext = json!({
+            "algorithm": BACKUP_SESSION_ALGORITHM,
+            "forwarding_curve25519_key_chain": forwarding_chain,
+            "sender_key": sender_key.inner.to_base64(),
+            "sender_claimed_keys": sender_claimed_keys,
+            "session_key": session.inner.export_at_first_known_index().to_base64(),
+        });
+
+        let message = self.encrypt(plaintext.to_string().as_bytes());
+        let (ciphertext, mac, ephemeral) = message.to_base64()?;
+
+        let mut session_data = Map::new();
+        session_data.insert("ephemeral".to_owned(), Value::String(ephemeral));
+        session_data.insert("ciphertext".to_owned(), Value::String(ciphertext));
+        session_data.insert("mac".to_owned(), Value::String(mac));
+
+        Ok(JsonObject::new(session_data).into_py(py)?)
+    }
 }
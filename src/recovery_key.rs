@@ -0,0 +1,53 @@
+use crate::error::RecoveryKeyError;
+
+/// The prefix of a recovery key, used to identify it as such.
+const PREFIX: [u8; 2] = [0x8B, 0x01];
+/// The length of a decoded recovery key, the prefix, the key and the parity
+/// byte.
+const LENGTH: usize = PREFIX.len() + 32 + 1;
+
+/// Encode a key as a [recovery key], split into groups of four characters
+/// separated by spaces.
+///
+/// [recovery key]: https://spec.matrix.org/v1.11/client-server-api/#recovery-key
+pub(crate) fn encode(key: &[u8; 32]) -> String {
+    let mut bytes = Vec::with_capacity(LENGTH);
+    bytes.extend_from_slice(&PREFIX);
+    bytes.extend_from_slice(key);
+    bytes.push(bytes.iter().fold(0, |parity, byte| parity ^ byte));
+
+    let encoded = bs58::encode(bytes).into_string();
+
+    encoded
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| std::str::from_utf8(chunk).expect("base58 is always valid UTF-8"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Decode a [recovery key], ignoring any whitespace in it.
+///
+/// [recovery key]: https://spec.matrix.org/v1.11/client-server-api/#recovery-key
+pub(crate) fn decode(recovery_key: &str) -> Result<[u8; 32], RecoveryKeyError> {
+    let recovery_key: String = recovery_key.chars().filter(|c| !c.is_whitespace()).collect();
+    let decoded = bs58::decode(recovery_key).into_vec()?;
+
+    if decoded.len() != LENGTH {
+        return Err(RecoveryKeyError::InvalidLength(decoded.len()));
+    }
+
+    if decoded[..PREFIX.len()] != PREFIX {
+        return Err(RecoveryKeyError::InvalidPrefix);
+    }
+
+    // XORing all the bytes, including the parity byte, results in 0 if the
+    // parity byte matches.
+    if decoded.iter().fold(0, |parity, byte| parity ^ byte) != 0 {
+        return Err(RecoveryKeyError::InvalidParity);
+    }
+
+    Ok(decoded[PREFIX.len()..LENGTH - 1]
+        .try_into()
+        .expect("the recovery key contains a 32 byte key"))
+}
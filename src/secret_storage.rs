@@ -0,0 +1,217 @@
+use aes::{
+    cipher::{KeyIvInit, StreamCipher},
+    Aes256,
+};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use pyo3::{prelude::*, types::PyType};
+use rand::{thread_rng, RngCore};
+use serde_json::{Map, Value};
+use sha2::{Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::{
+    canonical_json::JsonObject,
+    error::{RecoveryKeyError, SecretStorageError},
+    key_export::check_rounds,
+    recovery_key,
+};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+const KEY_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+
+/// A secret storage key, used to encrypt secrets, e.g. the cross-signing keys
+/// or the backup key, using the `m.secret_storage.v1.aes-hmac-sha2`
+/// [algorithm].
+///
+/// [algorithm]: https://spec.matrix.org/v1.11/client-server-api/#msecret_storagev1aes-hmac-sha2
+#[pyclass]
+pub struct SecretStorageKey {
+    key: Box<[u8; KEY_SIZE]>,
+}
+
+#[pymethods]
+impl SecretStorageKey {
+    /// Generate a new, random, secret storage key.
+    #[new]
+    fn new() -> Self {
+        let mut key = Box::new([0u8; KEY_SIZE]);
+        thread_rng().fill_bytes(key.as_mut());
+
+        Self { key }
+    }
+
+    /// Create a secret storage key from a recovery key.
+    #[classmethod]
+    fn from_recovery_key(
+        _cls: &Bound<'_, PyType>,
+        recovery_key: &str,
+    ) -> Result<Self, RecoveryKeyError> {
+        Ok(Self { key: Box::new(recovery_key::decode(recovery_key)?) })
+    }
+
+    /// Derive a secret storage key from a passphrase, using the salt and the
+    /// number of PBKDF2 iterations of the `passphrase` field of the key
+    /// description.
+    ///
+    /// The iterations are capped the same way as the rounds of a key export.
+    #[classmethod]
+    fn from_passphrase(
+        _cls: &Bound<'_, PyType>,
+        py: Python<'_>,
+        passphrase: &str,
+        salt: &str,
+        iterations: u32,
+    ) -> Result<Self, SecretStorageError> {
+        check_rounds(iterations)?;
+
+        let mut key = Box::new([0u8; KEY_SIZE]);
+        py.detach(|| {
+            pbkdf2::pbkdf2_hmac::<Sha512>(
+                passphrase.as_bytes(),
+                salt.as_bytes(),
+                iterations,
+                key.as_mut(),
+            )
+        });
+
+        Ok(Self { key })
+    }
+
+    /// Convert the secret storage key to a recovery key.
+    fn to_recovery_key(&self) -> String {
+        recovery_key::encode(&self.key)
+    }
+
+    /// Encrypt a secret with the given name, e.g. `m.cross_signing.master`.
+    ///
+    /// Returns the encrypted secret as a dict containing the `iv`, the
+    /// `ciphertext` and the `mac`.
+    fn encrypt_secret<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        plaintext: &str,
+    ) -> Result<Bound<'py, PyAny>, SecretStorageError> {
+        let iv = random_iv();
+        let (ciphertext, mac) = self.encrypt(name, plaintext.as_bytes(), &iv);
+
+        let mut encrypted = Map::new();
+        encrypted.insert("iv".to_owned(), Value::String(vodozemac::base64_encode(iv)));
+        encrypted
+            .insert("ciphertext".to_owned(), Value::String(vodozemac::base64_encode(ciphertext)));
+        encrypted.insert("mac".to_owned(), Value::String(vodozemac::base64_encode(mac)));
+
+        Ok(JsonObject::new(encrypted).into_py(py)?)
+    }
+
+    /// Decrypt a secret with the given name, the encrypted secret needs to
+    /// contain the `iv`, the `ciphertext` and the `mac`.
+    fn decrypt_secret(
+        &self,
+        name: &str,
+        encrypted: &Bound<'_, PyAny>,
+    ) -> Result<String, SecretStorageError> {
+        let encrypted = JsonObject::extract(encrypted)?;
+
+        let iv = decode_iv(field(&encrypted, "iv")?)?;
+        let ciphertext = vodozemac::base64_decode(field(&encrypted, "ciphertext")?)?;
+        let mac = vodozemac::base64_decode(field(&encrypted, "mac")?)?;
+
+        let (aes_key, mac_key) = self.derive_keys(name);
+
+        let mut hmac =
+            HmacSha256::new_from_slice(&mac_key).expect("HMAC can take a key of any size");
+        hmac.update(&ciphertext);
+        hmac.verify_slice(&mac).map_err(|_| SecretStorageError::InvalidMac)?;
+
+        let mut plaintext = ciphertext;
+        Aes256Ctr::new(&aes_key.into(), &iv.into()).apply_keystream(&mut plaintext);
+
+        Ok(String::from_utf8(plaintext)?)
+    }
+
+    /// Generate the `iv` and the `mac` of the key description, which are used
+    /// to check that a key is the correct secret storage key.
+    fn check_key<'py>(&self, py: Python<'py>) -> Result<Bound<'py, PyAny>, SecretStorageError> {
+        let iv = random_iv();
+        let (_, mac) = self.encrypt("", &[0u8; KEY_SIZE], &iv);
+
+        let mut check = Map::new();
+        check.insert("iv".to_owned(), Value::String(vodozemac::base64_encode(iv)));
+        check.insert("mac".to_owned(), Value::String(vodozemac::base64_encode(mac)));
+
+        Ok(JsonObject::new(check).into_py(py)?)
+    }
+
+    /// Check that this is the secret storage key of the given key
+    /// description, using its `iv` and `mac`.
+    fn verify_check_key(
+        &self,
+        key_description: &Bound<'_, PyAny>,
+    ) -> Result<bool, SecretStorageError> {
+        let key_description = JsonObject::extract(key_description)?;
+
+        let iv = decode_iv(field(&key_description, "iv")?)?;
+        let mac = vodozemac::base64_decode(field(&key_description, "mac")?)?;
+
+        let (_, expected_mac) = self.encrypt("", &[0u8; KEY_SIZE], &iv);
+
+        Ok(expected_mac.as_slice().ct_eq(&mac).into())
+    }
+}
+
+impl SecretStorageKey {
+    /// Derive the AES and the HMAC key for the secret with the given name.
+    fn derive_keys(&self, name: &str) -> ([u8; KEY_SIZE], [u8; KEY_SIZE]) {
+        let hkdf = Hkdf::<Sha256>::new(Some(&[0u8; 32]), self.key.as_slice());
+
+        let mut keys = [0u8; KEY_SIZE * 2];
+        hkdf.expand(name.as_bytes(), &mut keys).expect("64 bytes is a valid HKDF output length");
+
+        let mut aes_key = [0u8; KEY_SIZE];
+        let mut mac_key = [0u8; KEY_SIZE];
+        aes_key.copy_from_slice(&keys[..KEY_SIZE]);
+        mac_key.copy_from_slice(&keys[KEY_SIZE..]);
+
+        (aes_key, mac_key)
+    }
+
+    /// Encrypt the plaintext for the secret with the given name, returning
+    /// the ciphertext and its MAC.
+    fn encrypt(&self, name: &str, plaintext: &[u8], iv: &[u8; IV_SIZE]) -> (Vec<u8>, [u8; 32]) {
+        let (aes_key, mac_key) = self.derive_keys(name);
+
+        let mut ciphertext = plaintext.to_vec();
+        Aes256Ctr::new(&aes_key.into(), iv.into()).apply_keystream(&mut ciphertext);
+
+        let mut hmac =
+            HmacSha256::new_from_slice(&mac_key).expect("HMAC can take a key of any size");
+        hmac.update(&ciphertext);
+
+        (ciphertext, hmac.finalize().into_bytes().into())
+    }
+}
+
+/// Generate a random IV, with bit 63 cleared to avoid an overflow of the
+/// counter in implementations which only use the lower 64 bits as the
+/// counter.
+fn random_iv() -> [u8; IV_SIZE] {
+    let mut iv = [0u8; IV_SIZE];
+    thread_rng().fill_bytes(&mut iv);
+    iv[8] &= 0x7f;
+
+    iv
+}
+
+fn decode_iv(iv: &str) -> Result<[u8; IV_SIZE], SecretStorageError> {
+    let iv = vodozemac::base64_decode(iv)?;
+    iv.as_slice().try_into().map_err(|_| SecretStorageError::InvalidIvLength(iv.len()))
+}
+
+fn field<'a>(object: &'a JsonObject, name: &'static str) -> Result<&'a str, SecretStorageError> {
+    object.inner.get(name).and_then(Value::as_str).ok_or(SecretStorageError::MissingField(name))
+}
@@ -1,14 +1,67 @@
+use std::collections::HashSet;
+
 use pyo3::{
     prelude::*,
     types::{PyBytes, PyType},
 };
+use serde_json::{json, Map, Value};
 
 use crate::{
+    canonical_json::JsonObject,
     convert_to_pybytes,
-    types::{AnyOlmMessage, PreKeyMessage},
+    event_content::olm_message_to_json,
+    types::{AnyOlmMessage, Curve25519PublicKey, Ed25519PublicKey, PreKeyMessage, SessionKeys},
     LibolmPickleError, PickleError, SessionError,
 };
 
+/// The configuration for an Olm `Session`.
+///
+/// Version 1 truncates the MAC of each message to 8 bytes, version 2 uses the
+/// full-length MAC.
+#[pyclass(from_py_object)]
+#[derive(Clone)]
+pub struct OlmSessionConfig {
+    pub(crate) inner: vodozemac::olm::SessionConfig,
+}
+
+#[pymethods]
+impl OlmSessionConfig {
+    /// Create a config for the Olm version 1, using a truncated MAC.
+    #[classmethod]
+    fn version_1(_cls: &Bound<'_, PyType>) -> Self {
+        Self { inner: vodozemac::olm::SessionConfig::version_1() }
+    }
+
+    /// Create a config for the Olm version 2, using a full-length MAC.
+    #[classmethod]
+    fn version_2(_cls: &Bound<'_, PyType>) -> Self {
+        Self { inner: vodozemac::olm::SessionConfig::version_2() }
+    }
+
+    /// The numeric version of this config.
+    #[getter]
+    fn version(&self) -> u8 {
+        self.inner.version()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("OlmSessionConfig(version={})", self.inner.version())
+    }
+
+    #[classattr]
+    const __hash__: Option<Py<PyAny>> = None;
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl From<vodozemac::olm::SessionConfig> for OlmSessionConfig {
+    fn from(value: vodozemac::olm::SessionConfig) -> Self {
+        Self { inner: value }
+    }
+}
+
 #[pyclass]
 pub struct Session {
     pub(super) inner: vodozemac::olm::Session,
@@ -21,6 +74,11 @@ impl Session {
         self.inner.session_id()
     }
 
+    #[getter]
+    fn session_config(&self) -> OlmSessionConfig {
+        self.inner.session_config().into()
+    }
+
     fn pickle(&self, pickle_key: &[u8]) -> Result<String, PickleError> {
         let pickle_key: &[u8; 32] =
             pickle_key.try_into().map_err(|_| PickleError::InvalidKeySize(pickle_key.len()))?;
@@ -32,6 +90,28 @@ impl Session {
         self.inner.session_keys() == message.inner.session_keys()
     }
 
+    /// Has the session received a message from the other side yet.
+    ///
+    /// Sessions which have received a message should be preferred over the
+    /// ones which haven't, since the other side is known to use them.
+    #[getter]
+    fn has_received_message(&self) -> bool {
+        self.inner.has_received_message()
+    }
+
+    /// The keys which were used to establish the session.
+    fn session_keys(&self) -> SessionKeys {
+        self.inner.session_keys().into()
+    }
+
+    /// Describe the state of the ratchet chains of the session, meant for
+    /// debugging wedged sessions.
+    ///
+    /// The description doesn't contain any secret key material.
+    fn describe(&self) -> String {
+        format!("{:?}", self.inner)
+    }
+
     #[classmethod]
     fn from_pickle(
         _cls: &Bound<'_, PyType>,
@@ -67,3 +147,74 @@ impl Session {
         Ok(convert_to_pybytes(self.inner.decrypt(&message.inner)?.as_slice()))
     }
 }
+
+/// Encrypt the same payload for many devices at once, e.g. to share a room key.
+///
+/// Each entry of `sessions` contains the session with the device, the user ID
+/// of its owner and its Curve25519 and Ed25519 keys. The `recipient` and
+/// `recipient_keys` fields of the `plaintext_template` are filled in for each
+/// device before it's encrypted.
+///
+/// Returns the `ciphertext` map of the `m.olm.v1.curve25519-aes-sha2` event
+/// content, keyed by the Curve25519 key of each device. The GIL is released
+/// while encrypting.
+///
+/// Raises a `ValueError` if a device or a session is included more than once.
+#[pyfunction]
+pub fn encrypt_for_devices<'py>(
+    py: Python<'py>,
+    sessions: Vec<(Bound<'py, Session>, String, Curve25519PublicKey, PyRef<'py, Ed25519PublicKey>)>,
+    plaintext_template: &Bound<'py, PyAny>,
+) -> Result<Bound<'py, PyAny>, SessionError> {
+    let template = JsonObject::extract(plaintext_template)?.inner;
+
+    let mut seen_keys = HashSet::new();
+    let mut seen_sessions = HashSet::new();
+
+    for (session, _, curve25519_key, _) in &sessions {
+        if !seen_keys.insert(curve25519_key.inner) {
+            return Err(SessionError::DuplicateRecipient(curve25519_key.inner.to_base64()));
+        }
+
+        if !seen_sessions.insert(session.as_ptr()) {
+            let session_id = session.try_borrow().map_err(PyErr::from)?.inner.session_id();
+            return Err(SessionError::DuplicateSession(session_id));
+        }
+    }
+
+    let mut borrowed = sessions
+        .iter()
+        .map(|(session, ..)| session.try_borrow_mut())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PyErr::from)?;
+
+    let recipients: Vec<_> = borrowed
+        .iter_mut()
+        .zip(&sessions)
+        .map(|(session, (_, user_id, curve25519_key, ed25519_key))| {
+            (&mut session.inner, user_id.as_str(), curve25519_key.inner, ed25519_key.inner)
+        })
+        .collect();
+
+    let ciphertext: Map<String, Value> = py.detach(|| {
+        recipients
+            .into_iter()
+            .map(|(session, user_id, curve25519_key, ed25519_key)| {
+                let mut payload = template.clone();
+                payload.insert("recipient".to_owned(), Value::String(user_id.to_owned()));
+                payload.insert(
+                    "recipient_keys".to_owned(),
+                    json!({ "ed25519": ed25519_key.to_base64() }),
+                );
+
+                let plaintext = serde_json::to_string(&payload)
+                    .expect("a JSON object can always be serialized");
+                let message = session.encrypt(plaintext);
+
+                (curve25519_key.to_base64(), olm_message_to_json(&message))
+            })
+            .collect()
+    });
+
+    Ok(JsonObject::new(ciphertext).into_py(py)?)
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use pyo3::{
+    prelude::*,
+    types::{PyBytes, PyType},
+};
+use serde_json::{json, Value};
+use vodozemac::{
+    hazmat::Cipher,
+    olm::{OlmMessage, SessionConfig},
+};
+
+use crate::{
+    account::{Account, UsedKey},
+    convert_to_pybytes,
+    error::{PickleError, SessionError},
+    session::{OlmSessionConfig, Session},
+    types::{AnyOlmMessage, Curve25519PublicKey},
+};
+
+/// The default number of sessions which are kept per device.
+const DEFAULT_MAX_SESSIONS_PER_DEVICE: usize = 50;
+
+/// The result of decrypting a message with an `OlmSessionStore`.
+///
+/// If a new inbound session had to be created for a pre-key message, the
+/// result tells which of our keys the sender used, like the
+/// `InboundCreationResult` of `Account.create_inbound_session()`.
+#[pyclass]
+pub struct OlmDecryptionResult {
+    plaintext: Py<PyBytes>,
+    session_id: String,
+    used_key: Option<UsedKey>,
+}
+
+#[pymethods]
+impl OlmDecryptionResult {
+    #[getter]
+    fn plaintext(&self, py: Python<'_>) -> Py<PyBytes> {
+        self.plaintext.clone_ref(py)
+    }
+
+    /// The ID of the session which decrypted the message.
+    #[getter]
+    fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Was a new inbound session created to decrypt the message.
+    #[getter]
+    fn created_session(&self) -> bool {
+        self.used_key.is_some()
+    }
+
+    /// The base64-encoded ID of the one-time key or the fallback key which
+    /// was used to create the new session, `None` if no session was created
+    /// or the ID isn't known.
+    #[getter]
+    fn used_key_id(&self) -> Option<&str> {
+        self.used_key.as_ref()?.key_id.as_deref()
+    }
+
+    /// Was the new session created using our fallback key, in which case a
+    /// new fallback key should be generated.
+    #[getter]
+    fn used_fallback_key(&self) -> bool {
+        self.used_key.as_ref().is_some_and(|k| k.fallback)
+    }
+}
+
+/// A store for the Olm sessions with other devices, keyed by the Curve25519
+/// identity key of the device.
+///
+/// The store routes incoming messages to the right session, creating a new
+/// inbound session from the `Account` if no existing session matches a pre-key
+/// message. The sessions of each device are ordered by their last use, the
+/// least recently used sessions are dropped once a device has more than
+/// `max_sessions_per_device` sessions.
+#[pyclass]
+pub struct OlmSessionStore {
+    sessions: HashMap<String, Vec<vodozemac::olm::Session>>,
+    max_sessions_per_device: usize,
+}
+
+#[pymethods]
+impl OlmSessionStore {
+    #[new]
+    #[pyo3(signature = (max_sessions_per_device = DEFAULT_MAX_SESSIONS_PER_DEVICE))]
+    fn new(max_sessions_per_device: usize) -> Self {
+        Self { sessions: HashMap::new(), max_sessions_per_device: max_sessions_per_device.max(1) }
+    }
+
+    #[getter]
+    fn max_sessions_per_device(&self) -> usize {
+        self.max_sessions_per_device
+    }
+
+    /// The IDs of the sessions with the given device, the most recently used
+    /// session comes first.
+    fn session_ids(&self, identity_key: &Curve25519PublicKey) -> Vec<String> {
+        self.sessions
+            .get(&identity_key.inner.to_base64())
+            .map(|sessions| sessions.iter().map(|s| s.session_id()).collect())
+            .unwrap_or_default()
+    }
+
+    fn __len__(&self) -> usize {
+        self.sessions.values().map(Vec::len).sum()
+    }
+
+    /// Add an existing session with the given device to the store, as its
+    /// most recently used session, e.g. when migrating from another storage.
+    ///
+    /// The session is copied into the store, the given `Session` shouldn't be
+    /// used to encrypt or decrypt messages afterwards, since the copies would
+    /// diverge.
+    fn add_session(&mut self, identity_key: &Curve25519PublicKey, session: &Session) {
+        let session = vodozemac::olm::Session::from_pickle(session.inner.pickle());
+        let sessions = self.sessions.entry(identity_key.inner.to_base64()).or_default();
+        sessions.retain(|s| s.session_id() != session.session_id());
+
+        self.insert(identity_key.inner.to_base64(), session);
+    }
+
+    /// Get copies of the sessions with the given device, the most recently
+    /// used session comes first.
+    ///
+    /// Changes to the copies aren't reflected in the store, use the store to
+    /// encrypt and decrypt messages.
+    fn get_sessions(&self, identity_key: &Curve25519PublicKey) -> Vec<Session> {
+        self.sessions
+            .get(&identity_key.inner.to_base64())
+            .map(|sessions| {
+                sessions
+                    .iter()
+                    .map(|s| Session { inner: vodozemac::olm::Session::from_pickle(s.pickle()) })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remove all the sessions with the given device from the store, returning
+    /// them.
+    fn remove_sessions(&mut self, identity_key: &Curve25519PublicKey) -> Vec<Session> {
+        self.sessions
+            .remove(&identity_key.inner.to_base64())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|inner| Session { inner })
+            .collect()
+    }
+
+    /// Create a new outbound session with the given device and add it to the
+    /// store, returning the ID of the session.
+    #[pyo3(signature = (account, identity_key, one_time_key, session_config = None))]
+    fn create_outbound_session(
+        &mut self,
+        account: &Account,
+        identity_key: &Curve25519PublicKey,
+        one_time_key: &Curve25519PublicKey,
+        session_config: Option<OlmSessionConfig>,
+    ) -> String {
+        let session_config =
+            session_config.map(|c| c.inner).unwrap_or_else(SessionConfig::version_1);
+
+        let session = account.inner.create_outbound_session(
+            session_config,
+            identity_key.inner,
+            one_time_key.inner,
+        );
+        let session_id = session.session_id();
+
+        self.insert(identity_key.inner.to_base64(), session);
+
+        session_id
+    }
+
+    /// Encrypt the plaintext for the given device, using the most recently
+    /// used session with it.
+    ///
+    /// Returns `None` if there is no session with the device.
+    fn encrypt(
+        &mut self,
+        identity_key: &Curve25519PublicKey,
+        plaintext: &[u8],
+    ) -> Option<AnyOlmMessage> {
+        let session = self.sessions.get_mut(&identity_key.inner.to_base64())?.first_mut()?;
+
+        Some(AnyOlmMessage { inner: session.encrypt(plaintext) })
+    }
+
+    /// Decrypt a message from the given device.
+    ///
+    /// A pre-key message is decrypted using the session it belongs to, if the
+    /// store doesn't contain the session yet, a new inbound session is created
+    /// using the account. A normal message is decrypted by trying all the
+    /// sessions with the device, starting with the most recently used one.
+    fn decrypt(
+        &mut self,
+        account: &mut Account,
+        identity_key: &Curve25519PublicKey,
+        message: &AnyOlmMessage,
+    ) -> Result<OlmDecryptionResult, SessionError> {
+        let sender_key = identity_key.inner.to_base64();
+        let sessions =
+            self.sessions.get_mut(&sender_key).map(Vec::as_mut_slice).unwrap_or_default();
+
+        let (plaintext, session_id, used_key) = match &message.inner {
+            OlmMessage::PreKey(pre_key) => {
+                let session_keys = pre_key.session_keys();
+
+                if let Some(index) = sessions.iter().position(|s| s.session_keys() == session_keys)
+                {
+                    let plaintext = sessions[index].decrypt(&message.inner)?;
+                    sessions[..=index].rotate_right(1);
+
+                    (plaintext, sessions[0].session_id(), None)
+                } else {
+                    let (result, used_key) = account.create_inbound(identity_key.inner, pre_key)?;
+                    let session_id = result.session.session_id();
+                    self.insert(sender_key, result.session);
+
+                    (result.plaintext, session_id, Some(used_key))
+                }
+            }
+            OlmMessage::Normal(_) => {
+                let (index, plaintext) = sessions
+                    .iter_mut()
+                    .enumerate()
+                    .find_map(|(i, s)| s.decrypt(&message.inner).ok().map(|p| (i, p)))
+                    .ok_or(SessionError::NoMatchingSession(sender_key))?;
+                sessions[..=index].rotate_right(1);
+
+                (plaintext, sessions[0].session_id(), None)
+            }
+        };
+
+        Ok(OlmDecryptionResult { plaintext: convert_to_pybytes(&plaintext), session_id, used_key })
+    }
+
+    /// Pickle all the sessions of the store.
+    ///
+    /// The whole store, including the identity keys of the devices the
+    /// sessions belong to, is encrypted using the pickle key.
+    fn pickle(&self, pickle_key: &[u8]) -> Result<String, PickleError> {
+        let pickle_key: &[u8; 32] =
+            pickle_key.try_into().map_err(|_| PickleError::InvalidKeySize(pickle_key.len()))?;
+
+        let sessions: HashMap<&str, Vec<String>> = self
+            .sessions
+            .iter()
+            .map(|(sender_key, sessions)| {
+                (
+                    sender_key.as_str(),
+                    sessions.iter().map(|s| s.pickle().encrypt(pickle_key)).collect(),
+                )
+            })
+            .collect();
+
+        let pickle = json!({
+            "max_sessions_per_device": self.max_sessions_per_device,
+            "sessions": sessions,
+        });
+
+        // The sessions are encrypted on their own as well, so their secrets
+        // never end up in the plaintext of the store pickle.
+        let ciphertext =
+            Cipher::new_pickle(pickle_key).encrypt_pickle(pickle.to_string().as_bytes());
+
+        Ok(vodozemac::base64_encode(ciphertext))
+    }
+
+    #[classmethod]
+    fn from_pickle(
+        _cls: &Bound<'_, PyType>,
+        pickle: &str,
+        pickle_key: &[u8],
+    ) -> Result<Self, PickleError> {
+        let pickle_key: &[u8; 32] =
+            pickle_key.try_into().map_err(|_| PickleError::InvalidKeySize(pickle_key.len()))?;
+
+        let ciphertext = vodozemac::base64_decode(pickle).map_err(vodozemac::PickleError::from)?;
+        let plaintext = Cipher::new_pickle(pickle_key)
+            .decrypt_pickle(&ciphertext)
+            .map_err(vodozemac::PickleError::from)?;
+
+        let pickle: Value = serde_json::from_slice(&plaintext)?;
+        let max_sessions_per_device: usize =
+            serde_json::from_value(pickle["max_sessions_per_device"].clone())?;
+        let pickled_sessions: HashMap<String, Vec<String>> =
+            serde_json::from_value(pickle["sessions"].clone())?;
+
+        let mut sessions = HashMap::new();
+
+        for (sender_key, pickles) in pickled_sessions {
+            let device_sessions = pickles
+                .iter()
+                .map(|pickle| {
+                    let pickle = vodozemac::olm::SessionPickle::from_encrypted(pickle, pickle_key)?;
+                    Ok(vodozemac::olm::Session::from_pickle(pickle))
+                })
+                .collect::<Result<Vec<_>, PickleError>>()?;
+
+            sessions.insert(sender_key, device_sessions);
+        }
+
+        Ok(Self { sessions, max_sessions_per_device: max_sessions_per_device.max(1) })
+    }
+}
+
+impl OlmSessionStore {
+    /// Insert the session as the most recently used session of the device,
+    /// dropping the least recently used sessions above the limit.
+    fn insert(&mut self, sender_key: String, session: vodozemac::olm::Session) {
+        let sessions = self.sessions.entry(sender_key).or_default();
+
+        sessions.insert(0, session);
+        sessions.truncate(self.max_sessions_per_device);
+    }
+}
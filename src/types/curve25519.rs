@@ -2,9 +2,10 @@ use pyo3::{
     prelude::*,
     types::{PyBytes, PyType},
 };
+use subtle::ConstantTimeEq;
 use vodozemac::{base64_decode, base64_encode};
 
-use crate::{convert_to_pybytes, error::*};
+use crate::{convert_to_pybytes, error::*, recovery_key};
 
 #[pyclass(from_py_object)]
 #[derive(Clone)]
@@ -100,6 +101,32 @@ impl Curve25519SecretKey {
         Ok(Self { inner: vodozemac::Curve25519SecretKey::from_slice(key) })
     }
 
+    /// Create a `Curve25519SecretKey` from a [recovery key], the base58-encoded
+    /// form of a key backup decryption key which is shown to users.
+    ///
+    /// Whitespace in the recovery key is ignored. Raises a
+    /// `RecoveryKeyException` if the prefix or the parity byte of the
+    /// recovery key don't match.
+    ///
+    /// [recovery key]: https://spec.matrix.org/v1.11/client-server-api/#recovery-key
+    #[classmethod]
+    pub fn from_recovery_key(
+        _cls: &Bound<'_, PyType>,
+        recovery_key: &str,
+    ) -> Result<Self, RecoveryKeyError> {
+        let key = recovery_key::decode(recovery_key)?;
+
+        Ok(Self { inner: vodozemac::Curve25519SecretKey::from_slice(&key) })
+    }
+
+    /// Convert the `Curve25519SecretKey` to a [recovery key], split into
+    /// groups of four characters separated by spaces.
+    ///
+    /// [recovery key]: https://spec.matrix.org/v1.11/client-server-api/#recovery-key
+    pub fn to_recovery_key(&self) -> String {
+        recovery_key::encode(&self.inner.to_bytes())
+    }
+
     /// Convert the `Curve25519SecretKey` to a base64-encoded string.
     pub fn to_base64(&self) -> String {
         base64_encode(self.inner.to_bytes().as_slice())
@@ -115,4 +142,44 @@ impl Curve25519SecretKey {
     pub fn public_key(&self) -> Curve25519PublicKey {
         Curve25519PublicKey { inner: vodozemac::Curve25519PublicKey::from(&self.inner) }
     }
+
+    /// Perform a Diffie-Hellman key exchange with the given
+    /// `Curve25519PublicKey`.
+    ///
+    /// Raises a `KeyException` if the public key is a low-order point, since
+    /// the resulting shared secret would be insecure.
+    pub fn diffie_hellman(
+        &self,
+        public_key: &Curve25519PublicKey,
+    ) -> Result<SharedSecret, KeyError> {
+        let shared_secret = self.inner.diffie_hellman(&public_key.inner);
+
+        if shared_secret.was_contributory() {
+            Ok(SharedSecret { inner: shared_secret })
+        } else {
+            Err(vodozemac::KeyError::NonContributoryKey.into())
+        }
+    }
+}
+
+/// A shared secret, the result of a Curve25519 Diffie-Hellman key exchange.
+#[pyclass]
+pub struct SharedSecret {
+    inner: vodozemac::SharedSecret,
+}
+
+#[pymethods]
+impl SharedSecret {
+    /// Convert the `SharedSecret` to a byte array.
+    pub fn to_bytes(&self) -> Py<PyBytes> {
+        convert_to_pybytes(self.inner.as_bytes())
+    }
+
+    #[classattr]
+    const __hash__: Option<Py<PyAny>> = None;
+
+    /// Compare two shared secrets in constant time.
+    fn __eq__(&self, other: &Self) -> bool {
+        self.inner.as_bytes().ct_eq(other.inner.as_bytes()).into()
+    }
 }
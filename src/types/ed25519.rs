@@ -1,6 +1,9 @@
-use pyo3::{prelude::*, types::PyType};
+use pyo3::{
+    prelude::*,
+    types::{PyBytes, PyType},
+};
 
-use crate::error::*;
+use crate::{canonical_json::JsonObject, convert_to_pybytes, error::*};
 
 #[pyclass]
 pub struct Ed25519PublicKey {
@@ -28,6 +31,29 @@ impl Ed25519PublicKey {
         Ok(())
     }
 
+    /// Verify the signature of a JSON object, given as a dict or a JSON
+    /// string, following the Matrix signing rules.
+    ///
+    /// The signature is taken from the `signatures` field of the object,
+    /// using the given user ID and key ID, e.g. `ed25519:DEVICEID`.
+    pub fn verify_json(
+        &self,
+        object: &Bound<'_, PyAny>,
+        user_id: &str,
+        key_id: &str,
+    ) -> Result<(), JsonSignatureError> {
+        let object = JsonObject::extract(object)?;
+
+        let signature = object.signature(user_id, key_id).ok_or_else(|| {
+            JsonSignatureError::MissingSignature(user_id.to_owned(), key_id.to_owned())
+        })?;
+        let signature = vodozemac::Ed25519Signature::from_base64(signature)?;
+
+        self.inner.verify(object.canonical_json()?.as_bytes(), &signature)?;
+
+        Ok(())
+    }
+
     #[classattr]
     const __hash__: Option<Py<PyAny>> = None;
 
@@ -42,6 +68,88 @@ impl From<vodozemac::Ed25519PublicKey> for Ed25519PublicKey {
     }
 }
 
+/// An Ed25519 secret key, used to create digital signatures.
+#[pyclass]
+pub struct Ed25519SecretKey {
+    pub(crate) inner: vodozemac::Ed25519SecretKey,
+}
+
+impl From<vodozemac::Ed25519SecretKey> for Ed25519SecretKey {
+    fn from(value: vodozemac::Ed25519SecretKey) -> Self {
+        Self { inner: value }
+    }
+}
+
+#[pymethods]
+impl Ed25519SecretKey {
+    /// Generate a new, random, Ed25519SecretKey.
+    #[new]
+    fn new() -> Self {
+        Self { inner: vodozemac::Ed25519SecretKey::new() }
+    }
+
+    /// Create an `Ed25519SecretKey` from the given base64-encoded string.
+    #[classmethod]
+    pub fn from_base64(_cls: &Bound<'_, PyType>, key: &str) -> Result<Self, KeyError> {
+        Ok(Self { inner: vodozemac::Ed25519SecretKey::from_base64(key)? })
+    }
+
+    /// Create an `Ed25519SecretKey` from the given byte array.
+    #[classmethod]
+    pub fn from_bytes(_cls: &Bound<'_, PyType>, bytes: &[u8]) -> Result<Self, KeyError> {
+        let key: &[u8; 32] = bytes.try_into().map_err(|_| {
+            KeyError::from(vodozemac::KeyError::InvalidKeyLength {
+                key_type: "Ed25519SecretKey",
+                expected_length: 32,
+                length: bytes.len(),
+            })
+        })?;
+
+        Ok(Self { inner: vodozemac::Ed25519SecretKey::from_slice(key) })
+    }
+
+    /// Convert the `Ed25519SecretKey` to a base64-encoded string.
+    pub fn to_base64(&self) -> String {
+        self.inner.to_base64()
+    }
+
+    /// Convert the `Ed25519SecretKey` to a byte array.
+    pub fn to_bytes(&self) -> Py<PyBytes> {
+        convert_to_pybytes(self.inner.to_bytes().as_slice())
+    }
+
+    /// Give the `Ed25519PublicKey` associated with this `Ed25519SecretKey`.
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        self.inner.public_key().into()
+    }
+
+    /// Sign the given message with this `Ed25519SecretKey`.
+    pub fn sign(&self, message: &[u8]) -> Ed25519Signature {
+        self.inner.sign(message).into()
+    }
+
+    /// Sign a JSON object, given as a dict or a JSON string, following the
+    /// Matrix signing rules.
+    ///
+    /// The signature is added to the `signatures` field of the object under
+    /// the given user ID and key ID. The signed object is returned in the same
+    /// form it was given in.
+    pub fn sign_json<'py>(
+        &self,
+        object: &Bound<'py, PyAny>,
+        user_id: &str,
+        key_id: &str,
+    ) -> Result<Bound<'py, PyAny>, JsonSignatureError> {
+        let py = object.py();
+        let mut object = JsonObject::extract(object)?;
+
+        let signature = self.inner.sign(object.canonical_json()?.as_bytes());
+        object.add_signature(user_id, key_id, signature.to_base64())?;
+
+        object.into_py(py)
+    }
+}
+
 #[pyclass]
 pub struct Ed25519Signature {
     inner: vodozemac::Ed25519Signature,
@@ -1,6 +1,6 @@
 use pyo3::{prelude::*, types::PyType};
 
-use super::Ed25519Signature;
+use super::{Ed25519PublicKey, Ed25519Signature};
 use crate::error::*;
 
 #[pyclass]
@@ -39,6 +39,27 @@ impl MegolmMessage {
     pub fn signature(&self) -> Ed25519Signature {
         (*self.inner.signature()).into()
     }
+
+    pub fn ciphertext(&self) -> Vec<u8> {
+        self.inner.ciphertext().to_vec()
+    }
+
+    /// The MAC of the message, it is truncated to 8 bytes for sessions using
+    /// version 1 of the Megolm session config.
+    pub fn mac(&self) -> Vec<u8> {
+        self.inner.mac().to_vec()
+    }
+
+    /// Verify that the message was signed by the given key, i.e. the signing
+    /// key of the Megolm session, without decrypting the message.
+    pub fn verify_signature(&self, signing_key: &Ed25519PublicKey) -> Result<(), SignatureError> {
+        let message = self.inner.to_bytes();
+        let signed = &message[..message.len() - vodozemac::Ed25519Signature::LENGTH];
+
+        signing_key.inner.verify(signed, self.inner.signature())?;
+
+        Ok(())
+    }
 }
 
 impl From<vodozemac::megolm::MegolmMessage> for MegolmMessage {
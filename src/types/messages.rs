@@ -3,6 +3,7 @@ use pyo3::{
     types::{PyBytes, PyType},
 };
 
+use super::{Curve25519PublicKey, SessionKeys};
 use crate::{convert_to_pybytes, error::*};
 
 #[pyclass]
@@ -30,6 +31,14 @@ impl AnyOlmMessage {
         }
     }
 
+    pub fn to_normal(&self) -> Option<NormalOlmMessage> {
+        if let vodozemac::olm::OlmMessage::Normal(message) = &self.inner {
+            Some(NormalOlmMessage { inner: message.clone() })
+        } else {
+            None
+        }
+    }
+
     #[classmethod]
     pub fn from_parts(
         _cls: &Bound<'_, PyType>,
@@ -45,6 +54,93 @@ impl AnyOlmMessage {
     }
 }
 
+/// A normal Olm message, sent once the session has been established.
+#[pyclass]
+pub struct NormalOlmMessage {
+    pub(crate) inner: vodozemac::olm::Message,
+}
+
+#[pymethods]
+impl NormalOlmMessage {
+    #[classmethod]
+    pub fn from_base64(_cls: &Bound<'_, PyType>, message: &str) -> Result<Self, DecodeError> {
+        Ok(Self { inner: vodozemac::olm::Message::from_base64(message)? })
+    }
+
+    #[classmethod]
+    pub fn from_bytes(_cls: &Bound<'_, PyType>, message: &[u8]) -> Result<Self, DecodeError> {
+        Ok(Self { inner: vodozemac::olm::Message::from_bytes(message)? })
+    }
+
+    pub fn to_base64(&self) -> String {
+        self.inner.to_base64()
+    }
+
+    pub fn to_bytes(&self) -> Py<PyBytes> {
+        convert_to_pybytes(&self.inner.to_bytes())
+    }
+
+    pub fn to_any(&self) -> AnyOlmMessage {
+        AnyOlmMessage { inner: vodozemac::olm::OlmMessage::Normal(self.inner.clone()) }
+    }
+
+    /// The public part of the ratchet key which was used to encrypt the
+    /// message.
+    #[getter]
+    pub fn ratchet_key(&self) -> Curve25519PublicKey {
+        self.inner.ratchet_key().into()
+    }
+
+    /// The index of the message key in the chain of the ratchet key.
+    #[getter]
+    pub fn chain_index(&self) -> u64 {
+        self.inner.chain_index()
+    }
+
+    #[getter]
+    pub fn ciphertext(&self) -> Py<PyBytes> {
+        convert_to_pybytes(self.inner.ciphertext())
+    }
+
+    /// The MAC of the message, it is truncated to 8 bytes in version 3 of the
+    /// protocol.
+    #[getter]
+    pub fn mac(&self) -> Py<PyBytes> {
+        let mac_length = if self.inner.mac_truncated() { 8 } else { 32 };
+        let bytes = self.inner.to_bytes();
+
+        convert_to_pybytes(&bytes[bytes.len() - mac_length..])
+    }
+
+    /// The protocol version of the message.
+    #[getter]
+    pub fn version(&self) -> u8 {
+        self.inner.version()
+    }
+
+    /// Is the MAC of the message truncated.
+    #[getter]
+    pub fn mac_truncated(&self) -> bool {
+        self.inner.mac_truncated()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "NormalOlmMessage(version={}, ratchet_key={}, chain_index={}, mac_truncated={})",
+            self.inner.version(),
+            self.inner.ratchet_key().to_base64(),
+            self.inner.chain_index(),
+            if self.inner.mac_truncated() { "True" } else { "False" },
+        )
+    }
+}
+
+impl From<NormalOlmMessage> for AnyOlmMessage {
+    fn from(value: NormalOlmMessage) -> Self {
+        Self { inner: vodozemac::olm::OlmMessage::Normal(value.inner) }
+    }
+}
+
 #[pyclass]
 pub struct PreKeyMessage {
     pub(crate) inner: vodozemac::olm::PreKeyMessage,
@@ -61,9 +157,57 @@ impl PreKeyMessage {
         AnyOlmMessage { inner: vodozemac::olm::OlmMessage::PreKey(self.inner.clone()) }
     }
 
+    #[classmethod]
+    pub fn from_bytes(_cls: &Bound<'_, PyType>, message: &[u8]) -> Result<Self, DecodeError> {
+        Ok(Self { inner: vodozemac::olm::PreKeyMessage::from_bytes(message)? })
+    }
+
+    pub fn to_base64(&self) -> String {
+        self.inner.to_base64()
+    }
+
+    pub fn to_bytes(&self) -> Py<PyBytes> {
+        convert_to_pybytes(&self.inner.to_bytes())
+    }
+
     pub fn session_id(&self) -> String {
         self.inner.session_id()
     }
+
+    /// The keys which establish the session the message belongs to.
+    pub fn session_keys(&self) -> SessionKeys {
+        self.inner.session_keys().into()
+    }
+
+    /// The long-term identity key of the sender.
+    #[getter]
+    pub fn identity_key(&self) -> Curve25519PublicKey {
+        self.inner.identity_key().into()
+    }
+
+    /// The ephemeral base key the sender used to establish the session.
+    #[getter]
+    pub fn base_key(&self) -> Curve25519PublicKey {
+        self.inner.base_key().into()
+    }
+
+    /// Our one-time key the sender used to establish the session.
+    #[getter]
+    pub fn one_time_key(&self) -> Curve25519PublicKey {
+        self.inner.one_time_key().into()
+    }
+
+    /// The normal message which is embedded in this pre-key message.
+    #[getter]
+    pub fn message(&self) -> NormalOlmMessage {
+        NormalOlmMessage { inner: self.inner.message().clone() }
+    }
+
+    /// The protocol version of the embedded message.
+    #[getter]
+    pub fn version(&self) -> u8 {
+        self.inner.message().version()
+    }
 }
 
 impl From<PreKeyMessage> for AnyOlmMessage {
@@ -1,5 +1,6 @@
 use pyo3::prelude::*;
 
+use super::Curve25519PublicKey;
 use crate::error::*;
 
 #[pyclass]
@@ -47,3 +48,58 @@ impl From<vodozemac::megolm::ExportedSessionKey> for ExportedSessionKey {
         Self { inner: value }
     }
 }
+
+/// The set of keys which were used to establish an Olm session.
+#[pyclass]
+pub struct SessionKeys {
+    pub(crate) inner: vodozemac::olm::SessionKeys,
+}
+
+#[pymethods]
+impl SessionKeys {
+    /// The long-term identity key of the session initiator.
+    #[getter]
+    pub fn identity_key(&self) -> Curve25519PublicKey {
+        self.inner.identity_key.into()
+    }
+
+    /// The ephemeral base key the initiator created to establish the session.
+    #[getter]
+    pub fn base_key(&self) -> Curve25519PublicKey {
+        self.inner.base_key.into()
+    }
+
+    /// The one-time key of the recipient, which was used to establish the
+    /// session.
+    #[getter]
+    pub fn one_time_key(&self) -> Curve25519PublicKey {
+        self.inner.one_time_key.into()
+    }
+
+    /// The ID of the session these keys establish.
+    pub fn session_id(&self) -> String {
+        self.inner.session_id()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SessionKeys(identity_key={}, base_key={}, one_time_key={})",
+            self.inner.identity_key.to_base64(),
+            self.inner.base_key.to_base64(),
+            self.inner.one_time_key.to_base64(),
+        )
+    }
+
+    #[classattr]
+    const __hash__: Option<Py<PyAny>> = None;
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl From<vodozemac::olm::SessionKeys> for SessionKeys {
+    fn from(value: vodozemac::olm::SessionKeys) -> Self {
+        Self { inner: value }
+    }
+}
@@ -0,0 +1,785 @@
+use std::collections::BTreeMap;
+
+use pyo3::prelude::*;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use vodozemac::{
+    sas::{EstablishedSas, Sas},
+    Curve25519PublicKey,
+};
+
+use crate::{canonical_json::JsonObject, error::SasVerificationError};
+
+const METHOD: &str = "m.sas.v1";
+const HASH: &str = "sha256";
+
+const START: &str = "m.key.verification.start";
+const ACCEPT: &str = "m.key.verification.accept";
+const KEY: &str = "m.key.verification.key";
+const MAC: &str = "m.key.verification.mac";
+const DONE: &str = "m.key.verification.done";
+const CANCEL: &str = "m.key.verification.cancel";
+
+/// The key agreement protocols we support, in order of preference.
+const KEY_AGREEMENT_PROTOCOLS: &[&str] = &["curve25519-hkdf-sha256", "curve25519"];
+/// The MAC methods we support, in order of preference.
+const MESSAGE_AUTHENTICATION_CODES: &[&str] = &["hkdf-hmac-sha256.v2", "hkdf-hmac-sha256"];
+const SHORT_AUTHENTICATION_STRINGS: &[&str] = &["decimal", "emoji"];
+
+/// An event which needs to be sent to the other side, the event type and its
+/// content.
+type Outgoing = (&'static str, Map<String, Value>);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Created,
+    Started,
+    Accepted,
+    KeysExchanged,
+    Verified,
+    Cancelled,
+}
+
+impl State {
+    fn as_str(self) -> &'static str {
+        match self {
+            State::Created => "created",
+            State::Started => "started",
+            State::Accepted => "accepted",
+            State::KeysExchanged => "keys_exchanged",
+            State::Verified => "verified",
+            State::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// The reason why a verification was cancelled.
+struct Cancellation {
+    code: String,
+    reason: String,
+}
+
+impl Cancellation {
+    fn new(code: &str, reason: impl Into<String>) -> Self {
+        Self { code: code.to_owned(), reason: reason.into() }
+    }
+
+    fn unexpected(event_type: &str) -> Self {
+        Self::new("m.unexpected_message", format!("Unexpected {event_type} event"))
+    }
+
+    fn invalid(field: &str) -> Self {
+        Self::new("m.invalid_message", format!("The {field} field is missing or invalid"))
+    }
+}
+
+struct Device {
+    user_id: String,
+    device_id: String,
+    keys: BTreeMap<String, String>,
+}
+
+/// The methods both sides agreed on in the `m.key.verification.accept` event.
+struct Agreement {
+    key_agreement_protocol: String,
+    message_authentication_code: String,
+    short_authentication_string: Vec<String>,
+}
+
+struct Established {
+    sas: EstablishedSas,
+    their_public_key: Curve25519PublicKey,
+}
+
+/// An interactive `m.sas.v1` key verification with another device.
+///
+/// The object is fed the contents of the `m.key.verification.*` to-device
+/// events the other side sent us, and returns the events which need to be
+/// sent to the other side as a list of `(event_type, content)` tuples.
+///
+/// The verification either starts by calling `start()`, or by receiving an
+/// `m.key.verification.start` event and calling `accept()`. If both sides
+/// started the verification, the start event of the side with the lower user
+/// ID, or device ID, is used and the other side needs to call `accept()`, as
+/// `we_started` tells. Once the keys have been exchanged, the short
+/// authentication string is available and needs to be compared by the users,
+/// after which `confirm()` sends our MAC. The verification ends in the
+/// `verified` or the `cancelled` state.
+#[pyclass]
+pub struct SasVerification {
+    own: Device,
+    other: Device,
+    transaction_id: String,
+    state: State,
+    we_started: bool,
+    sas: Option<Sas>,
+    public_key: Curve25519PublicKey,
+    start_content: Option<JsonObject>,
+    agreement: Option<Agreement>,
+    commitment: Option<String>,
+    established: Option<Established>,
+    confirmed: bool,
+    verified_keys: Option<Vec<String>>,
+    done_sent: bool,
+    done_received: bool,
+    cancellation: Option<Cancellation>,
+}
+
+#[pymethods]
+impl SasVerification {
+    /// Create a new verification between our device and the other device.
+    ///
+    /// The `own_keys` are the keys we want the other side to verify, and the
+    /// `other_keys` the keys of the other side we expect to be verified, both
+    /// as dicts from key ID, e.g. `ed25519:DEVICEID`, to the base64-encoded
+    /// key.
+    #[new]
+    fn new(
+        own_user_id: String,
+        own_device_id: String,
+        own_keys: BTreeMap<String, String>,
+        other_user_id: String,
+        other_device_id: String,
+        other_keys: BTreeMap<String, String>,
+        transaction_id: String,
+    ) -> Self {
+        let sas = Sas::new();
+        let public_key = sas.public_key();
+
+        Self {
+            own: Device { user_id: own_user_id, device_id: own_device_id, keys: own_keys },
+            other: Device { user_id: other_user_id, device_id: other_device_id, keys: other_keys },
+            transaction_id,
+            state: State::Created,
+            we_started: false,
+            sas: Some(sas),
+            public_key,
+            start_content: None,
+            agreement: None,
+            commitment: None,
+            established: None,
+            confirmed: false,
+            verified_keys: None,
+            done_sent: false,
+            done_received: false,
+            cancellation: None,
+        }
+    }
+
+    #[getter]
+    fn transaction_id(&self) -> &str {
+        &self.transaction_id
+    }
+
+    /// The state of the verification, one of `created`, `started`,
+    /// `accepted`, `keys_exchanged`, `verified` or `cancelled`.
+    #[getter]
+    fn state(&self) -> &'static str {
+        self.state.as_str()
+    }
+
+    /// Did we send the `m.key.verification.start` event.
+    #[getter]
+    fn we_started(&self) -> bool {
+        self.we_started
+    }
+
+    #[getter]
+    fn is_verified(&self) -> bool {
+        self.state == State::Verified
+    }
+
+    #[getter]
+    fn is_cancelled(&self) -> bool {
+        self.state == State::Cancelled
+    }
+
+    /// The cancellation code, if the verification was cancelled by either
+    /// side.
+    #[getter]
+    fn cancel_code(&self) -> Option<&str> {
+        self.cancellation.as_ref().map(|c| c.code.as_str())
+    }
+
+    #[getter]
+    fn cancel_reason(&self) -> Option<&str> {
+        self.cancellation.as_ref().map(|c| c.reason.as_str())
+    }
+
+    /// The short authentication string methods both sides agreed on.
+    #[getter]
+    fn short_authentication_string(&self) -> Vec<String> {
+        self.agreement.as_ref().map(|a| a.short_authentication_string.clone()).unwrap_or_default()
+    }
+
+    /// The emoji indices which need to be compared, available once the keys
+    /// have been exchanged and if both sides support the `emoji` method.
+    #[getter]
+    fn emoji_indices(&self) -> Option<[u8; 7]> {
+        let agreement = self.agreement.as_ref()?;
+
+        if agreement.short_authentication_string.iter().any(|s| s == "emoji") {
+            let established = self.established.as_ref()?;
+            Some(established.sas.bytes(&self.sas_info(established)).emoji_indices())
+        } else {
+            None
+        }
+    }
+
+    /// The decimals which need to be compared, available once the keys have
+    /// been exchanged.
+    #[getter]
+    fn decimals(&self) -> Option<(u16, u16, u16)> {
+        let established = self.established.as_ref()?;
+        Some(established.sas.bytes(&self.sas_info(established)).decimals())
+    }
+
+    /// The key IDs of the other side which were verified by their MAC.
+    #[getter]
+    fn verified_keys(&self) -> Vec<String> {
+        self.verified_keys.clone().unwrap_or_default()
+    }
+
+    /// Start the verification, returning the `m.key.verification.start`
+    /// event.
+    fn start<'py>(
+        &mut self,
+        py: Python<'py>,
+    ) -> Result<Vec<(&'static str, Bound<'py, PyAny>)>, SasVerificationError> {
+        self.check_state("start", self.state == State::Created)?;
+
+        let content = into_object(json!({
+            "from_device": self.own.device_id,
+            "method": METHOD,
+            "transaction_id": self.transaction_id,
+            "key_agreement_protocols": KEY_AGREEMENT_PROTOCOLS,
+            "hashes": [HASH],
+            "message_authentication_codes": MESSAGE_AUTHENTICATION_CODES,
+            "short_authentication_string": SHORT_AUTHENTICATION_STRINGS,
+        }));
+
+        self.start_content = Some(JsonObject::new(content.clone()));
+        self.we_started = true;
+        self.state = State::Started;
+
+        into_py(py, vec![(START, content)])
+    }
+
+    /// Accept a verification the other side started, returning the
+    /// `m.key.verification.accept` event.
+    fn accept<'py>(
+        &mut self,
+        py: Python<'py>,
+    ) -> Result<Vec<(&'static str, Bound<'py, PyAny>)>, SasVerificationError> {
+        self.check_state("accept", self.state == State::Started && !self.we_started)?;
+
+        let (Some(start_content), Some(agreement)) = (&self.start_content, &self.agreement) else {
+            return Err(SasVerificationError::InvalidState("accept", self.state.as_str()));
+        };
+
+        let content = into_object(json!({
+            "transaction_id": self.transaction_id,
+            "method": METHOD,
+            "key_agreement_protocol": agreement.key_agreement_protocol,
+            "hash": HASH,
+            "message_authentication_code": agreement.message_authentication_code,
+            "short_authentication_string": agreement.short_authentication_string,
+            "commitment": commitment(self.public_key, start_content)?,
+        }));
+
+        self.state = State::Accepted;
+
+        into_py(py, vec![(ACCEPT, content)])
+    }
+
+    /// Confirm that the short authentication strings match, returning the
+    /// `m.key.verification.mac` event, followed by the
+    /// `m.key.verification.done` event if the MAC of the other side was
+    /// already received.
+    fn confirm<'py>(
+        &mut self,
+        py: Python<'py>,
+    ) -> Result<Vec<(&'static str, Bound<'py, PyAny>)>, SasVerificationError> {
+        self.check_state("confirm", self.state == State::KeysExchanged && !self.confirmed)?;
+
+        let Some(established) = &self.established else {
+            return Err(SasVerificationError::InvalidState("confirm", self.state.as_str()));
+        };
+
+        let info = mac_info(&self.own, &self.other, &self.transaction_id);
+        let mut mac = Map::new();
+
+        for (key_id, key) in &self.own.keys {
+            mac.insert(
+                key_id.clone(),
+                Value::String(self.calculate_mac(established, key, &format!("{info}{key_id}"))),
+            );
+        }
+
+        let key_ids = self.own.keys.keys().cloned().collect::<Vec<_>>().join(",");
+        let keys = self.calculate_mac(established, &key_ids, &format!("{info}KEY_IDS"));
+
+        let content = into_object(json!({
+            "transaction_id": self.transaction_id,
+            "mac": mac,
+            "keys": keys,
+        }));
+
+        self.confirmed = true;
+
+        let mut events = vec![(MAC, content)];
+        events.extend(self.done());
+
+        into_py(py, events)
+    }
+
+    /// Cancel the verification, returning the `m.key.verification.cancel`
+    /// event.
+    #[pyo3(signature = (code = "m.user", reason = "The user cancelled the verification"))]
+    fn cancel<'py>(
+        &mut self,
+        py: Python<'py>,
+        code: &str,
+        reason: &str,
+    ) -> Result<Vec<(&'static str, Bound<'py, PyAny>)>, SasVerificationError> {
+        self.check_state("cancel", !matches!(self.state, State::Verified | State::Cancelled))?;
+
+        let event = self.cancel_with(Cancellation::new(code, reason));
+
+        into_py(py, vec![event])
+    }
+
+    /// Receive an `m.key.verification.*` event the other side sent us,
+    /// returning the events which need to be sent in response.
+    ///
+    /// Unexpected or invalid events cancel the verification, in which case
+    /// the `m.key.verification.cancel` event is returned. Events which are
+    /// received after the verification ended are ignored.
+    fn receive_event<'py>(
+        &mut self,
+        py: Python<'py>,
+        event_type: &str,
+        content: &Bound<'py, PyAny>,
+    ) -> Result<Vec<(&'static str, Bound<'py, PyAny>)>, SasVerificationError> {
+        let content = JsonObject::extract(content)?;
+
+        if content.inner.get("transaction_id").and_then(Value::as_str)
+            != Some(self.transaction_id.as_str())
+        {
+            return Err(SasVerificationError::TransactionMismatch(self.transaction_id.clone()));
+        }
+
+        if matches!(self.state, State::Verified | State::Cancelled) {
+            return Ok(Vec::new());
+        }
+
+        let result = match event_type {
+            START => self.receive_start(content),
+            ACCEPT => self.receive_accept(&content.inner),
+            KEY => self.receive_key(&content.inner),
+            MAC => self.receive_mac(&content.inner),
+            DONE => self.receive_done(),
+            CANCEL => {
+                self.receive_cancel(&content.inner);
+                Ok(Vec::new())
+            }
+            _ => Err(Cancellation::unexpected(event_type)),
+        };
+
+        let events = result.unwrap_or_else(|cancellation| vec![self.cancel_with(cancellation)]);
+
+        into_py(py, events)
+    }
+}
+
+impl SasVerification {
+    fn check_state(&self, action: &'static str, valid: bool) -> Result<(), SasVerificationError> {
+        if valid {
+            Ok(())
+        } else {
+            Err(SasVerificationError::InvalidState(action, self.state.as_str()))
+        }
+    }
+
+    fn receive_start(&mut self, content: JsonObject) -> Result<Vec<Outgoing>, Cancellation> {
+        if self.state == State::Started && self.we_started {
+            // Both sides started the verification, the side with the lower
+            // user ID, or the lower device ID for the same user, wins and the
+            // other side accepts its start event instead.
+            if (&self.own.user_id, &self.own.device_id)
+                < (&self.other.user_id, &self.other.device_id)
+            {
+                return Ok(Vec::new());
+            }
+
+            self.we_started = false;
+            self.start_content = None;
+        } else if self.state != State::Created {
+            return Err(Cancellation::unexpected(START));
+        }
+
+        if get_str(&content.inner, "method")? != METHOD {
+            return Err(Cancellation::new("m.unknown_method", "Only m.sas.v1 is supported"));
+        }
+
+        if get_str(&content.inner, "from_device")? != self.other.device_id {
+            return Err(Cancellation::new(
+                "m.user_mismatch",
+                "The verification was started by an unexpected device",
+            ));
+        }
+
+        let key_agreement_protocol = choose(
+            KEY_AGREEMENT_PROTOCOLS,
+            &get_strings(&content.inner, "key_agreement_protocols")?,
+        )?;
+        choose(&[HASH], &get_strings(&content.inner, "hashes")?)?;
+        let message_authentication_code = choose(
+            MESSAGE_AUTHENTICATION_CODES,
+            &get_strings(&content.inner, "message_authentication_codes")?,
+        )?;
+
+        let their_methods = get_strings(&content.inner, "short_authentication_string")?;
+        let short_authentication_string: Vec<String> = SHORT_AUTHENTICATION_STRINGS
+            .iter()
+            .filter(|method| their_methods.iter().any(|m| m == *method))
+            .map(|method| method.to_string())
+            .collect();
+
+        // The decimal method must be supported by every client.
+        if !short_authentication_string.iter().any(|m| m == "decimal") {
+            return Err(Cancellation::new(
+                "m.unknown_method",
+                "The decimal method isn't supported",
+            ));
+        }
+
+        self.agreement = Some(Agreement {
+            key_agreement_protocol,
+            message_authentication_code,
+            short_authentication_string,
+        });
+        self.start_content = Some(content);
+        self.state = State::Started;
+
+        Ok(Vec::new())
+    }
+
+    fn receive_accept(
+        &mut self,
+        content: &Map<String, Value>,
+    ) -> Result<Vec<Outgoing>, Cancellation> {
+        if self.state != State::Started || !self.we_started {
+            return Err(Cancellation::unexpected(ACCEPT));
+        }
+
+        if get_str(content, "method")? != METHOD {
+            return Err(Cancellation::new("m.unknown_method", "Only m.sas.v1 is supported"));
+        }
+
+        let key_agreement_protocol = choose(
+            KEY_AGREEMENT_PROTOCOLS,
+            &[get_str(content, "key_agreement_protocol")?.to_owned()],
+        )?;
+        choose(&[HASH], &[get_str(content, "hash")?.to_owned()])?;
+        let message_authentication_code = choose(
+            MESSAGE_AUTHENTICATION_CODES,
+            &[get_str(content, "message_authentication_code")?.to_owned()],
+        )?;
+
+        let short_authentication_string = get_strings(content, "short_authentication_string")?;
+
+        if short_authentication_string.is_empty()
+            || short_authentication_string
+                .iter()
+                .any(|m| !SHORT_AUTHENTICATION_STRINGS.contains(&m.as_str()))
+        {
+            return Err(Cancellation::new(
+                "m.unknown_method",
+                "The short authentication string method isn't supported",
+            ));
+        }
+
+        self.agreement = Some(Agreement {
+            key_agreement_protocol,
+            message_authentication_code,
+            short_authentication_string,
+        });
+        self.commitment = Some(get_str(content, "commitment")?.to_owned());
+        self.state = State::Accepted;
+
+        Ok(vec![self.key_event()])
+    }
+
+    fn receive_key(&mut self, content: &Map<String, Value>) -> Result<Vec<Outgoing>, Cancellation> {
+        if self.state != State::Accepted {
+            return Err(Cancellation::unexpected(KEY));
+        }
+
+        let key = get_str(content, "key")?;
+        let their_public_key =
+            Curve25519PublicKey::from_base64(key).map_err(|_| Cancellation::invalid("key"))?;
+
+        // The side which started the verification needs to check that the key
+        // matches the commitment the other side sent in the accept event.
+        if self.we_started {
+            let (Some(start_content), Some(expected)) = (&self.start_content, &self.commitment)
+            else {
+                return Err(Cancellation::unexpected(KEY));
+            };
+
+            let commitment = commitment(their_public_key, start_content)
+                .map_err(|_| Cancellation::invalid("key"))?;
+
+            if !bool::from(commitment.as_bytes().ct_eq(expected.as_bytes())) {
+                return Err(Cancellation::new(
+                    "m.mismatched_commitment",
+                    "The key doesn't match the commitment",
+                ));
+            }
+        }
+
+        let sas = self.sas.take().ok_or_else(|| Cancellation::unexpected(KEY))?;
+        let sas = sas.diffie_hellman(their_public_key).map_err(|_| Cancellation::invalid("key"))?;
+
+        self.established = Some(Established { sas, their_public_key });
+        self.state = State::KeysExchanged;
+
+        // The side which accepted the verification sends its key only after
+        // receiving the key of the other side.
+        if self.we_started {
+            Ok(Vec::new())
+        } else {
+            Ok(vec![self.key_event()])
+        }
+    }
+
+    fn receive_mac(&mut self, content: &Map<String, Value>) -> Result<Vec<Outgoing>, Cancellation> {
+        let (State::KeysExchanged, None, Some(established)) =
+            (self.state, &self.verified_keys, &self.established)
+        else {
+            return Err(Cancellation::unexpected(MAC));
+        };
+
+        let mac = content
+            .get("mac")
+            .and_then(Value::as_object)
+            .ok_or_else(|| Cancellation::invalid("mac"))?;
+        let keys = get_str(content, "keys")?;
+
+        let info = mac_info(&self.other, &self.own, &self.transaction_id);
+
+        // serde_json's `Map` is sorted by key, so the key IDs are already in
+        // the order the MAC of the key IDs expects.
+        let key_ids = mac.keys().cloned().collect::<Vec<_>>().join(",");
+
+        if !self.verify_mac(established, &key_ids, &format!("{info}KEY_IDS"), keys) {
+            return Err(Cancellation::new(
+                "m.key_mismatch",
+                "The MAC of the key IDs doesn't match",
+            ));
+        }
+
+        let mut verified_keys = Vec::new();
+
+        for (key_id, tag) in mac {
+            // Keys we don't know about are ignored.
+            let Some(key) = self.other.keys.get(key_id) else {
+                continue;
+            };
+
+            let tag = tag.as_str().ok_or_else(|| Cancellation::invalid("mac"))?;
+
+            if !self.verify_mac(established, key, &format!("{info}{key_id}"), tag) {
+                return Err(Cancellation::new(
+                    "m.key_mismatch",
+                    format!("The MAC of the key {key_id} doesn't match"),
+                ));
+            }
+
+            verified_keys.push(key_id.clone());
+        }
+
+        if verified_keys.is_empty() {
+            return Err(Cancellation::new("m.key_mismatch", "None of the keys could be verified"));
+        }
+
+        self.verified_keys = Some(verified_keys);
+
+        Ok(self.done().into_iter().collect())
+    }
+
+    fn receive_done(&mut self) -> Result<Vec<Outgoing>, Cancellation> {
+        // The other side can only be done once it received our MAC.
+        if self.state != State::KeysExchanged || !self.confirmed || self.done_received {
+            return Err(Cancellation::unexpected(DONE));
+        }
+
+        self.done_received = true;
+
+        Ok(self.done().into_iter().collect())
+    }
+
+    fn receive_cancel(&mut self, content: &Map<String, Value>) {
+        let code = content.get("code").and_then(Value::as_str).unwrap_or("m.unknown");
+        let reason = content.get("reason").and_then(Value::as_str).unwrap_or_default();
+
+        self.cancellation = Some(Cancellation::new(code, reason));
+        self.state = State::Cancelled;
+    }
+
+    /// Send the `m.key.verification.done` event if we confirmed the short
+    /// authentication string and verified the MAC of the other side, and
+    /// mark the verification as verified once both sides are done.
+    fn done(&mut self) -> Option<Outgoing> {
+        let event = if self.confirmed && self.verified_keys.is_some() && !self.done_sent {
+            self.done_sent = true;
+            Some((DONE, into_object(json!({ "transaction_id": self.transaction_id }))))
+        } else {
+            None
+        };
+
+        if self.done_sent && self.done_received {
+            self.state = State::Verified;
+        }
+
+        event
+    }
+
+    fn cancel_with(&mut self, cancellation: Cancellation) -> Outgoing {
+        let content = into_object(json!({
+            "transaction_id": self.transaction_id,
+            "code": cancellation.code,
+            "reason": cancellation.reason,
+        }));
+
+        self.cancellation = Some(cancellation);
+        self.state = State::Cancelled;
+
+        (CANCEL, content)
+    }
+
+    fn key_event(&self) -> Outgoing {
+        let content = into_object(json!({
+            "transaction_id": self.transaction_id,
+            "key": self.public_key.to_base64(),
+        }));
+
+        (KEY, content)
+    }
+
+    /// The info used to derive the short authentication string, this depends
+    /// on the key agreement protocol.
+    fn sas_info(&self, established: &Established) -> String {
+        let (initiator, initiator_key, acceptor, acceptor_key) = if self.we_started {
+            (&self.own, self.public_key, &self.other, established.their_public_key)
+        } else {
+            (&self.other, established.their_public_key, &self.own, self.public_key)
+        };
+
+        let key_agreement_protocol =
+            self.agreement.as_ref().map(|a| a.key_agreement_protocol.as_str());
+
+        if key_agreement_protocol == Some("curve25519-hkdf-sha256") {
+            format!(
+                "MATRIX_KEY_VERIFICATION_SAS|{}|{}|{}|{}|{}|{}|{}",
+                initiator.user_id,
+                initiator.device_id,
+                initiator_key.to_base64(),
+                acceptor.user_id,
+                acceptor.device_id,
+                acceptor_key.to_base64(),
+                self.transaction_id,
+            )
+        } else {
+            format!(
+                "MATRIX_KEY_VERIFICATION_SAS{}{}{}{}{}",
+                initiator.user_id,
+                initiator.device_id,
+                acceptor.user_id,
+                acceptor.device_id,
+                self.transaction_id,
+            )
+        }
+    }
+
+    /// Calculate a MAC using the agreed upon method, the original
+    /// `hkdf-hmac-sha256` method uses a broken base64 encoding.
+    fn calculate_mac(&self, established: &Established, input: &str, info: &str) -> String {
+        let message_authentication_code =
+            self.agreement.as_ref().map(|a| a.message_authentication_code.as_str());
+
+        if message_authentication_code == Some("hkdf-hmac-sha256") {
+            established.sas.calculate_mac_invalid_base64(input, info)
+        } else {
+            established.sas.calculate_mac(input, info).to_base64()
+        }
+    }
+
+    fn verify_mac(&self, established: &Established, input: &str, info: &str, tag: &str) -> bool {
+        self.calculate_mac(established, input, info).as_bytes().ct_eq(tag.as_bytes()).into()
+    }
+}
+
+/// The info used to derive the MAC of the keys the sender sends to the
+/// receiver, the key ID needs to be appended to it.
+fn mac_info(sender: &Device, receiver: &Device, transaction_id: &str) -> String {
+    format!(
+        "MATRIX_KEY_VERIFICATION_MAC{}{}{}{}{}",
+        sender.user_id, sender.device_id, receiver.user_id, receiver.device_id, transaction_id
+    )
+}
+
+/// Calculate the commitment of the accepting side, the unpadded base64 of the
+/// SHA-256 hash of its public key concatenated with the canonical JSON of the
+/// start event content.
+fn commitment(
+    public_key: Curve25519PublicKey,
+    start_content: &JsonObject,
+) -> Result<String, SasVerificationError> {
+    let mut hasher = Sha256::new();
+    hasher.update(public_key.to_base64());
+    hasher.update(start_content.canonical_json()?);
+
+    Ok(vodozemac::base64_encode(hasher.finalize()))
+}
+
+/// Choose our most preferred method out of the methods the other side
+/// supports.
+fn choose(ours: &[&str], theirs: &[String]) -> Result<String, Cancellation> {
+    ours.iter()
+        .find(|method| theirs.iter().any(|m| m == *method))
+        .map(|method| method.to_string())
+        .ok_or_else(|| Cancellation::new("m.unknown_method", "No common method is supported"))
+}
+
+fn get_str<'a>(content: &'a Map<String, Value>, field: &str) -> Result<&'a str, Cancellation> {
+    content.get(field).and_then(Value::as_str).ok_or_else(|| Cancellation::invalid(field))
+}
+
+fn get_strings(content: &Map<String, Value>, field: &str) -> Result<Vec<String>, Cancellation> {
+    content
+        .get(field)
+        .and_then(Value::as_array)
+        .and_then(|values| values.iter().map(|v| v.as_str().map(str::to_owned)).collect())
+        .ok_or_else(|| Cancellation::invalid(field))
+}
+
+/// Convert an event content created with `json!` into a JSON object.
+fn into_object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(object) => object,
+        _ => unreachable!("event contents are always JSON objects"),
+    }
+}
+
+fn into_py(
+    py: Python<'_>,
+    events: Vec<Outgoing>,
+) -> Result<Vec<(&'static str, Bound<'_, PyAny>)>, SasVerificationError> {
+    events
+        .into_iter()
+        .map(|(event_type, content)| Ok((event_type, JsonObject::new(content).into_py(py)?)))
+        .collect()
+}
@@ -1,6 +1,13 @@
 import pytest
 from hypothesis import given
-from vodozemac import Account, PickleException, SignatureException, Ed25519PublicKey, Curve25519PublicKey
+from vodozemac import (
+    Account,
+    DehydratedDeviceException,
+    PickleException,
+    SignatureException,
+    Ed25519PublicKey,
+    Curve25519PublicKey,
+)
 
 @pytest.fixture(scope="module")
 def account() -> Account:
@@ -18,6 +25,116 @@ def test_generate_and_publish_one_time_keys(account: Account):
     account.mark_keys_as_published()
     assert not account.one_time_keys
 
+def test_one_time_key_generation_result():
+    account = Account()
+    # The account stores up to 100 times the number of keys it publishes.
+    max_stored_keys = 100 * account.max_number_of_one_time_keys
+
+    first = account.generate_one_time_keys(max_stored_keys)
+    assert len(first.created) == max_stored_keys
+    assert first.removed == []
+    assert account.stored_one_time_key_count == max_stored_keys
+
+    # The key IDs match the ones of the unpublished keys.
+    one_time_keys = account.one_time_keys
+    assert [one_time_keys[key_id] for key_id in first.created_key_ids] == first.created
+
+    result = account.generate_one_time_keys(2)
+    assert len(result.created) == 2
+    assert result.removed == first.created[:2]
+    assert result.removed_key_ids == first.created_key_ids[:2]
+    assert account.stored_one_time_key_count == max_stored_keys
+
+    # The IDs of removed keys which were published aren't known.
+    account.mark_keys_as_published()
+    result = account.generate_one_time_keys(1)
+    assert result.removed == first.created[2:3]
+    assert result.removed_key_ids == [None]
+    assert [account.one_time_keys[key_id] for key_id in result.created_key_ids] == result.created
+
+def test_replenish_one_time_keys():
+    account = Account()
+    target = account.max_number_of_one_time_keys // 2
+
+    result = account.replenish_one_time_keys(0)
+    assert len(result.created) == target
+    assert len(account.replenish_one_time_keys(0).created) == 0
+
+    account.mark_keys_as_published()
+    assert len(account.replenish_one_time_keys(target - 5).created) == 5
+    assert len(account.replenish_one_time_keys(target + 5).created) == 0
+    assert account.stored_one_time_key_count == target + 5
+
+def test_forget_fallback_key():
+    account = Account()
+    assert not account.forget_fallback_key()
+
+    account.generate_fallback_key()
+    account.mark_keys_as_published()
+    assert not account.forget_fallback_key()
+
+    account.generate_fallback_key()
+    assert account.forget_fallback_key()
+    assert not account.forget_fallback_key()
+
+def test_inbound_creation_result():
+    alice = Account()
+    bob = Account()
+
+    bob.generate_one_time_keys(1)
+    key_id, one_time_key = next(iter(bob.one_time_keys.items()))
+    bob.generate_fallback_key()
+    fallback_key_id, fallback_key = next(iter(bob.fallback_key.items()))
+
+    session = alice.create_outbound_session(bob.curve25519_key, one_time_key)
+    message = session.encrypt(b"Hello").to_pre_key()
+    result = bob.create_inbound_session(alice.curve25519_key, message)
+
+    assert result.plaintext == b"Hello"
+    assert result.session.session_id == session.session_id
+    assert result.used_key_id == key_id
+    assert not result.used_fallback_key
+    assert bob.stored_one_time_key_count == 0
+
+    session, plaintext = result
+    assert plaintext == b"Hello"
+    assert session.session_id == result.session.session_id
+    assert len(result) == 2
+    assert result[0].session_id == session.session_id
+    assert result[1] == result[-1] == b"Hello"
+
+    with pytest.raises(IndexError):
+        result[2]
+
+    session = alice.create_outbound_session(bob.curve25519_key, fallback_key)
+    message = session.encrypt(b"Hello again").to_pre_key()
+    result = bob.create_inbound_session(alice.curve25519_key, message)
+
+    assert result.plaintext == b"Hello again"
+    assert result.used_key_id == fallback_key_id
+    assert result.used_fallback_key
+
+def test_published_key_ids():
+    alice = Account()
+    bob = Account()
+
+    bob.generate_one_time_keys(1)
+    one_time_key = next(iter(bob.one_time_keys.values()))
+    bob.generate_fallback_key()
+    fallback_key = next(iter(bob.fallback_key.values()))
+    bob.mark_keys_as_published()
+
+    # The IDs of the published keys aren't known, but the kind of key is.
+    message = alice.create_outbound_session(bob.curve25519_key, one_time_key).encrypt(b"Hi")
+    result = bob.create_inbound_session(alice.curve25519_key, message.to_pre_key())
+    assert result.used_key_id is None
+    assert not result.used_fallback_key
+
+    message = alice.create_outbound_session(bob.curve25519_key, fallback_key).encrypt(b"Hi")
+    result = bob.create_inbound_session(alice.curve25519_key, message.to_pre_key())
+    assert result.used_key_id is None
+    assert result.used_fallback_key
+
 def test_pickling(account: Account, pickle_key: bytes):
     pickle = account.pickle(pickle_key)
     unpickled = Account.from_pickle(pickle, pickle_key)
@@ -41,9 +158,101 @@ def test_invalid_pickle(pickle_key: bytes):
     with pytest.raises(PickleException):
         Account.from_pickle("", pickle_key)
 
+def test_dehydrated_device(pickle_key: bytes):
+    account = Account()
+    account.generate_one_time_keys(10)
+    one_time_key = next(iter(account.one_time_keys.values()))
+
+    ciphertext, nonce = account.to_dehydrated_device(pickle_key)
+    rehydrated = Account.from_dehydrated_device(ciphertext, nonce, pickle_key)
+
+    assert account.ed25519_key == rehydrated.ed25519_key
+    assert account.curve25519_key == rehydrated.curve25519_key
+
+    # The rehydrated device still holds the private parts of its one-time keys.
+    sender = Account()
+    session = sender.create_outbound_session(rehydrated.curve25519_key, one_time_key)
+    message = session.encrypt(b"It's a secret to everybody").to_pre_key()
+
+    _, plaintext = rehydrated.create_inbound_session(sender.curve25519_key, message)
+    assert plaintext == b"It's a secret to everybody"
+
+def test_dehydrated_device_failures(pickle_key: bytes):
+    account = Account()
+    ciphertext, nonce = account.to_dehydrated_device(pickle_key)
+
+    with pytest.raises(DehydratedDeviceException):
+        account.to_dehydrated_device(b"Too short")
+
+    with pytest.raises(DehydratedDeviceException):
+        Account.from_dehydrated_device(ciphertext, nonce, b"It's a secret to everybody 12345")
+
+    with pytest.raises(DehydratedDeviceException):
+        Account.from_dehydrated_device(ciphertext, "AAAA", pickle_key)
+
 @given(message=...)
 def test_signing(account: Account, message: bytes):
     signature = account.sign(message)
     account.ed25519_key.verify_signature(message, signature)
     with pytest.raises(SignatureException):
         account.ed25519_key.verify_signature(b"This should fail", signature)
+
+
+def test_sign_json(account: Account):
+    device_keys = {
+        "user_id": "@alice:example.org",
+        "device_id": "DEVICEID",
+        "keys": {"curve25519:DEVICEID": account.curve25519_key.to_base64()},
+        "signatures": {"@alice:example.org": {"ed25519:OTHER": "existing signature"}},
+    }
+
+    signed = account.sign_json(device_keys, "@alice:example.org", "ed25519:DEVICEID")
+
+    assert signed["signatures"]["@alice:example.org"]["ed25519:OTHER"] == "existing signature"
+    account.ed25519_key.verify_json(signed, "@alice:example.org", "ed25519:DEVICEID")
+
+    with pytest.raises(SignatureException):
+        Account().ed25519_key.verify_json(signed, "@alice:example.org", "ed25519:DEVICEID")
+
+def test_device_keys():
+    account = Account()
+    device_keys = account.device_keys("@alice:example.org", "DEVICEID")
+
+    assert device_keys["user_id"] == "@alice:example.org"
+    assert device_keys["device_id"] == "DEVICEID"
+    assert device_keys["algorithms"] == ["m.olm.v1.curve25519-aes-sha2", "m.megolm.v1.aes-sha2"]
+    assert device_keys["keys"] == {
+        "curve25519:DEVICEID": account.curve25519_key.to_base64(),
+        "ed25519:DEVICEID": account.ed25519_key.to_base64(),
+    }
+    account.ed25519_key.verify_json(device_keys, "@alice:example.org", "ed25519:DEVICEID")
+
+    device_keys = account.device_keys("@alice:example.org", "DEVICEID", ["m.olm.v1.curve25519-aes-sha2"])
+    assert device_keys["algorithms"] == ["m.olm.v1.curve25519-aes-sha2"]
+
+def test_signed_one_time_keys():
+    account = Account()
+    account.generate_one_time_keys(5)
+    account.generate_fallback_key()
+
+    one_time_keys = account.signed_one_time_keys("@alice:example.org", "DEVICEID")
+    assert len(one_time_keys) == 5
+
+    for key_id, key in account.one_time_keys.items():
+        signed_key = one_time_keys[f"signed_curve25519:{key_id}"]
+        assert signed_key["key"] == key.to_base64()
+        assert "fallback" not in signed_key
+        account.ed25519_key.verify_json(signed_key, "@alice:example.org", "ed25519:DEVICEID")
+
+    fallback_keys = account.signed_fallback_keys("@alice:example.org", "DEVICEID")
+    assert len(fallback_keys) == 1
+
+    for key_id, key in account.fallback_key.items():
+        signed_key = fallback_keys[f"signed_curve25519:{key_id}"]
+        assert signed_key["key"] == key.to_base64()
+        assert signed_key["fallback"] is True
+        account.ed25519_key.verify_json(signed_key, "@alice:example.org", "ed25519:DEVICEID")
+
+    account.mark_keys_as_published()
+    assert account.signed_one_time_keys("@alice:example.org", "DEVICEID") == {}
+    assert account.signed_fallback_keys("@alice:example.org", "DEVICEID") == {}
@@ -0,0 +1,94 @@
+import hashlib
+from base64 import b64decode, urlsafe_b64decode
+
+import pytest
+from hypothesis import given
+from vodozemac import (
+    AttachmentDecryptor,
+    AttachmentEncryptor,
+    AttachmentException,
+    AttachmentHashMismatchException,
+)
+
+ENCRYPTED_FILE = {
+    "v": "v2",
+    "key": {
+        "kty": "oct",
+        "key_ops": ["encrypt", "decrypt"],
+        "alg": "A256CTR",
+        "k": "__________________________________________8",
+        "ext": True,
+    },
+    "iv": "//////////8AAAAAAAAAAA",
+    "hashes": {"sha256": "kFifvUARBL6EPM1lF1jC9pV3kFtc7yWXKZdsFKMvYo4"},
+}
+CIPHERTEXT = b64decode("5xJZTt5cQicm+9f48Q==")
+
+
+def encrypt(plaintext: bytes, chunk_size: int = 7):
+    encryptor = AttachmentEncryptor()
+    chunks = [plaintext[i:i + chunk_size] for i in range(0, len(plaintext), chunk_size)]
+    ciphertext = b"".join(encryptor.update(chunk) for chunk in chunks)
+    return ciphertext, encryptor.finalize()
+
+def test_decrypt_test_vector():
+    decryptor = AttachmentDecryptor(ENCRYPTED_FILE)
+    plaintext = decryptor.update(CIPHERTEXT[:5]) + decryptor.update(CIPHERTEXT[5:])
+    decryptor.finalize()
+
+    assert plaintext == b"Hello, World!"
+
+@given(plaintext=...)
+def test_encrypt_decrypt(plaintext: bytes):
+    ciphertext, encrypted_file = encrypt(plaintext)
+
+    decryptor = AttachmentDecryptor(encrypted_file)
+    assert decryptor.update(ciphertext) == plaintext
+    decryptor.finalize()
+
+def test_encrypted_file_metadata():
+    ciphertext, encrypted_file = encrypt(b"It's a secret to everybody")
+
+    assert encrypted_file["v"] == "v2"
+    assert encrypted_file["key"]["alg"] == "A256CTR"
+    assert encrypted_file["key"]["kty"] == "oct"
+    assert len(urlsafe_b64decode(encrypted_file["key"]["k"] + "=")) == 32
+    assert b64decode(encrypted_file["iv"] + "==")[8:] == bytes(8)
+
+    sha256 = b64decode(encrypted_file["hashes"]["sha256"] + "=")
+    assert sha256 == hashlib.sha256(ciphertext).digest()
+
+def test_hash_mismatch():
+    ciphertext, encrypted_file = encrypt(b"It's a secret to everybody")
+
+    decryptor = AttachmentDecryptor(encrypted_file)
+    decryptor.update(ciphertext[:-1] + bytes([ciphertext[-1] ^ 1]))
+
+    with pytest.raises(AttachmentHashMismatchException):
+        decryptor.finalize()
+
+def test_finalized():
+    encryptor = AttachmentEncryptor()
+    encryptor.finalize()
+
+    with pytest.raises(AttachmentException, match="finalized"):
+        encryptor.update(b"chunk")
+
+    with pytest.raises(AttachmentException, match="finalized"):
+        encryptor.finalize()
+
+def test_invalid_encrypted_file():
+    with pytest.raises(AttachmentException, match="v1"):
+        AttachmentDecryptor({**ENCRYPTED_FILE, "v": "v1"})
+
+    with pytest.raises(AttachmentException, match="A128CTR"):
+        AttachmentDecryptor({**ENCRYPTED_FILE, "key": {**ENCRYPTED_FILE["key"], "alg": "A128CTR"}})
+
+    with pytest.raises(AttachmentException, match="hashes"):
+        AttachmentDecryptor({key: value for key, value in ENCRYPTED_FILE.items() if key != "hashes"})
+
+    with pytest.raises(AttachmentException, match="IV"):
+        AttachmentDecryptor({**ENCRYPTED_FILE, "iv": "AAAA"})
+
+    with pytest.raises(AttachmentException, match="JSON"):
+        AttachmentDecryptor("not json")
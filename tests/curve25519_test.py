@@ -0,0 +1,89 @@
+import pytest
+from functools import reduce
+from vodozemac import (
+    Curve25519PublicKey,
+    Curve25519SecretKey,
+    KeyException,
+    PkDecryption,
+    RecoveryKeyException,
+    SharedSecret,
+)
+
+BASE58_ALPHABET = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"
+
+
+def base58_encode(data: bytes) -> str:
+    number = int.from_bytes(data, "big")
+    encoded = ""
+
+    while number:
+        number, remainder = divmod(number, 58)
+        encoded = BASE58_ALPHABET[remainder] + encoded
+
+    leading_zeros = len(data) - len(data.lstrip(b"\0"))
+    return "1" * leading_zeros + encoded
+
+
+def recovery_key(prefix: bytes, key: bytes, parity_mask: int = 0) -> str:
+    data = prefix + key
+    parity = reduce(lambda a, b: a ^ b, data) ^ parity_mask
+    return base58_encode(data + bytes([parity]))
+
+
+def test_diffie_hellman():
+    alice = Curve25519SecretKey()
+    bob = Curve25519SecretKey()
+
+    alice_secret = alice.diffie_hellman(bob.public_key())
+    bob_secret = bob.diffie_hellman(alice.public_key())
+
+    assert isinstance(alice_secret, SharedSecret)
+    assert alice_secret == bob_secret
+    assert alice_secret.to_bytes() == bob_secret.to_bytes()
+    assert len(alice_secret.to_bytes()) == 32
+
+def test_different_secrets():
+    alice = Curve25519SecretKey()
+    bob = Curve25519SecretKey()
+    eve = Curve25519SecretKey()
+
+    assert alice.diffie_hellman(bob.public_key()) != alice.diffie_hellman(eve.public_key())
+
+def test_low_order_point():
+    low_order_key = Curve25519PublicKey.from_bytes(bytes(32))
+
+    with pytest.raises(KeyException):
+        Curve25519SecretKey().diffie_hellman(low_order_key)
+
+def test_recovery_key():
+    key = Curve25519SecretKey()
+    encoded = key.to_recovery_key()
+
+    assert encoded.startswith("Es")
+    assert all(len(group) == 4 for group in encoded.split(" ")[:-1])
+    assert encoded.replace(" ", "") == recovery_key(b"\x8b\x01", key.to_bytes())
+
+    decoded = Curve25519SecretKey.from_recovery_key(encoded)
+    assert decoded.to_bytes() == key.to_bytes()
+
+def test_recovery_key_whitespace():
+    key = Curve25519SecretKey()
+    encoded = f"  {key.to_recovery_key().replace(' ', '')}\n"
+
+    decoded = Curve25519SecretKey.from_recovery_key(encoded)
+    assert PkDecryption.from_key(decoded).public_key == key.public_key()
+
+def test_invalid_recovery_key():
+    key = Curve25519SecretKey().to_bytes()
+
+    with pytest.raises(RecoveryKeyException, match="prefix"):
+        Curve25519SecretKey.from_recovery_key(recovery_key(b"\x8b\x02", key))
+
+    with pytest.raises(RecoveryKeyException, match="length"):
+        Curve25519SecretKey.from_recovery_key(recovery_key(b"\x8b\x01", key[:31]))
+
+    with pytest.raises(RecoveryKeyException, match="parity"):
+        Curve25519SecretKey.from_recovery_key(recovery_key(b"\x8b\x01", key, parity_mask=1))
+
+    with pytest.raises(RecoveryKeyException):
+        Curve25519SecretKey.from_recovery_key("0OIl")
@@ -0,0 +1,91 @@
+import pytest
+from hypothesis import given
+from vodozemac import (
+    CheckCode,
+    Curve25519PublicKey,
+    DecodeException,
+    Ecies,
+    EciesException,
+    EciesMessage,
+    EstablishedEcies,
+    InitialMessage,
+)
+
+INITIAL_MESSAGE = (
+    "3On7QFJyLQMAErua9K/yIOcJALvuMYax1AW0iWgf64AwtSMZXwAA012Q"
+    "|9yA/CX8pJKF02Prd75ZyBQHg3fGTVVGDNl86q1z17Us"
+)
+
+
+def establish_channels() -> tuple[EstablishedEcies, EstablishedEcies]:
+    alice = Ecies()
+    bob = Ecies()
+
+    alice_established, initial_message = alice.establish_outbound_channel(
+        bob.public_key, b"It's a secret to everybody"
+    )
+    initial_message = InitialMessage.from_base64(initial_message.to_base64())
+    bob_established, plaintext = bob.establish_inbound_channel(initial_message)
+
+    assert plaintext == b"It's a secret to everybody"
+
+    return alice_established, bob_established
+
+@pytest.fixture
+def channels() -> tuple[EstablishedEcies, EstablishedEcies]:
+    return establish_channels()
+
+def test_creation():
+    ecies = Ecies()
+    assert isinstance(ecies.public_key, Curve25519PublicKey)
+
+def test_check_code(channels: tuple[EstablishedEcies, EstablishedEcies]):
+    alice, bob = channels
+
+    assert isinstance(alice.check_code, CheckCode)
+    assert alice.check_code == bob.check_code
+    assert alice.check_code.as_bytes() == bob.check_code.as_bytes()
+    assert 0 <= alice.check_code.to_digit() < 100
+
+@given(message=...)
+def test_round_trip(message: bytes):
+    alice, bob = establish_channels()
+
+    encrypted = EciesMessage.from_base64(alice.encrypt(message).to_base64())
+    assert bob.decrypt(encrypted) == message
+
+    assert alice.decrypt(bob.encrypt(message)) == message
+
+def test_custom_info():
+    alice = Ecies("MY_APPLICATION")
+    bob = Ecies()
+
+    _, initial_message = alice.establish_outbound_channel(bob.public_key, b"Test")
+
+    with pytest.raises(EciesException):
+        bob.establish_inbound_channel(initial_message)
+
+def test_replayed_message(channels: tuple[EstablishedEcies, EstablishedEcies]):
+    alice, bob = channels
+    message = alice.encrypt(b"Test")
+
+    assert bob.decrypt(message) == b"Test"
+    with pytest.raises(EciesException):
+        bob.decrypt(message)
+
+def test_used_twice():
+    alice = Ecies()
+    bob = Ecies()
+
+    alice.establish_outbound_channel(bob.public_key, b"Test")
+    with pytest.raises(EciesException):
+        alice.establish_outbound_channel(bob.public_key, b"Test")
+
+def test_initial_message_decoding():
+    message = InitialMessage.from_base64(INITIAL_MESSAGE)
+
+    assert message.public_key.to_base64() == "9yA/CX8pJKF02Prd75ZyBQHg3fGTVVGDNl86q1z17Us"
+    assert message.to_base64() == INITIAL_MESSAGE
+
+    with pytest.raises(DecodeException):
+        InitialMessage.from_base64("no separator")
@@ -0,0 +1,103 @@
+import json
+from base64 import b64decode
+
+import pytest
+from hypothesis import given
+from vodozemac import (
+    Account,
+    Ed25519PublicKey,
+    Ed25519SecretKey,
+    Ed25519Signature,
+    KeyException,
+    SignatureException,
+)
+
+
+@pytest.fixture(scope="module")
+def secret_key() -> Ed25519SecretKey:
+    return Ed25519SecretKey()
+
+def test_creation(secret_key: Ed25519SecretKey):
+    assert isinstance(secret_key.public_key(), Ed25519PublicKey)
+    assert Ed25519SecretKey().public_key() != secret_key.public_key()
+
+def test_serialization(secret_key: Ed25519SecretKey):
+    from_bytes = Ed25519SecretKey.from_bytes(secret_key.to_bytes())
+    from_base64 = Ed25519SecretKey.from_base64(secret_key.to_base64())
+
+    assert from_bytes.public_key() == secret_key.public_key()
+    assert from_base64.public_key() == secret_key.public_key()
+
+def test_invalid_key():
+    with pytest.raises(KeyException):
+        Ed25519SecretKey.from_bytes(b"Too short")
+
+    with pytest.raises(KeyException):
+        Ed25519SecretKey.from_base64("Too short")
+
+@given(message=...)
+def test_signing(secret_key: Ed25519SecretKey, message: bytes):
+    signature = secret_key.sign(message)
+    secret_key.public_key().verify_signature(message, signature)
+
+    signature = Ed25519Signature.from_base64(signature.to_base64())
+    secret_key.public_key().verify_signature(message, signature)
+
+    with pytest.raises(SignatureException):
+        secret_key.public_key().verify_signature(b"This should fail", signature)
+
+    with pytest.raises(SignatureException):
+        Account().ed25519_key.verify_signature(message, signature)
+
+
+def test_sign_json_spec_vectors():
+    # The signing key and signatures from the examples in the Matrix spec appendix.
+    secret_key = Ed25519SecretKey.from_bytes(
+        b64decode("YJDBA9Xnr2sVqXD9Vj7XVUnmFZcZrlw8Md7kMW+3XA1=")
+    )
+
+    signed = secret_key.sign_json({}, "domain", "ed25519:1")
+    assert signed == {
+        "signatures": {
+            "domain": {
+                "ed25519:1": "K8280/U9SSy9IVtjBuVeLr+HpOB4BQFWbg+UZaADMtTdGYI7Geitb76LTrr5QV/7Xg4ahLwYGYZzuHGZKM5ZAQ"
+            }
+        }
+    }
+
+    signed = secret_key.sign_json('{"one": 1, "two": "Two"}', "domain", "ed25519:1")
+    assert isinstance(signed, str)
+    assert json.loads(signed) == {
+        "one": 1,
+        "two": "Two",
+        "signatures": {
+            "domain": {
+                "ed25519:1": "KqmLSbO39/Bzb0QIYE82zqLwsA+PDzYIpIRA2sRQ4sL53+sN6/fpNSoqE7BP7vBZhG6kYdD13EIMJpvhJI+6Bw"
+            }
+        },
+    }
+
+    secret_key.public_key().verify_json(signed, "domain", "ed25519:1")
+
+def test_verify_json(secret_key: Ed25519SecretKey):
+    signed = secret_key.sign_json({"b": [1, 2], "a": "It's a secret"}, "@alice:example.org", "ed25519:KEY")
+
+    # The order of the keys and the unsigned field don't matter.
+    reordered = {"unsigned": {"age": 1}, **dict(reversed(list(signed.items())))}
+    secret_key.public_key().verify_json(reordered, "@alice:example.org", "ed25519:KEY")
+
+    with pytest.raises(SignatureException):
+        secret_key.public_key().verify_json(signed, "@alice:example.org", "ed25519:OTHER")
+
+    with pytest.raises(SignatureException):
+        secret_key.public_key().verify_json({**signed, "a": "Tampered"}, "@alice:example.org", "ed25519:KEY")
+
+def test_sign_invalid_json(secret_key: Ed25519SecretKey):
+    with pytest.raises(ValueError):
+        secret_key.sign_json({"float": 1.5}, "@alice:example.org", "ed25519:KEY")
+
+    with pytest.raises(ValueError):
+        secret_key.sign_json("[1, 2, 3]", "@alice:example.org", "ed25519:KEY")
+
+    with pytest.raises(ValueError):
+        secret_key.sign_json("{not json", "@alice:example.org", "ed25519:KEY")
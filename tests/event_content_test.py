@@ -0,0 +1,107 @@
+import json
+
+import pytest
+from vodozemac import (
+    Account,
+    EncryptedContentException,
+    GroupSession,
+    InboundGroupSession,
+    MegolmEncryptedContent,
+    OlmEncryptedContent,
+    parse_encrypted_content,
+)
+
+
+def test_olm_content():
+    alice, bob = Account(), Account()
+    bob.generate_one_time_keys(1)
+    one_time_key = next(iter(bob.one_time_keys.values()))
+    session = alice.create_outbound_session(bob.curve25519_key, one_time_key)
+
+    message = session.encrypt(b"It's a secret to everybody")
+    content = OlmEncryptedContent(alice.curve25519_key, {bob.curve25519_key.to_base64(): message})
+
+    content_dict = content.to_dict()
+    assert content_dict["algorithm"] == "m.olm.v1.curve25519-aes-sha2"
+    assert content_dict["sender_key"] == alice.curve25519_key.to_base64()
+    assert content_dict["ciphertext"][bob.curve25519_key.to_base64()]["type"] == 0
+
+    parsed = parse_encrypted_content(json.dumps(content_dict))
+    assert isinstance(parsed, OlmEncryptedContent)
+    assert parsed.algorithm == "m.olm.v1.curve25519-aes-sha2"
+    assert parsed.sender_key == alice.curve25519_key
+    assert list(parsed.ciphertext) == [bob.curve25519_key.to_base64()]
+    assert parsed.message_for(alice.curve25519_key) is None
+
+    received = parsed.message_for(bob.curve25519_key)
+    _, plaintext = bob.create_inbound_session(parsed.sender_key, received.to_pre_key())
+    assert plaintext == b"It's a secret to everybody"
+
+def test_megolm_content():
+    outbound = GroupSession()
+    inbound = InboundGroupSession(outbound.session_key)
+    sender_key = Account().curve25519_key
+
+    message = outbound.encrypt(b"Hello")
+    content = MegolmEncryptedContent(message, outbound.session_id)
+
+    content_dict = content.to_dict()
+    assert content_dict == {
+        "algorithm": "m.megolm.v1.aes-sha2",
+        "ciphertext": message.to_base64(),
+        "session_id": outbound.session_id,
+    }
+
+    parsed = parse_encrypted_content(content_dict)
+    assert isinstance(parsed, MegolmEncryptedContent)
+    assert parsed.session_id == outbound.session_id
+    assert parsed.sender_key is None
+    assert parsed.device_id is None
+    assert inbound.decrypt(parsed.ciphertext).plaintext == b"Hello"
+
+    content = MegolmEncryptedContent(
+        outbound.encrypt(b"Hello"), outbound.session_id, sender_key, "DEVICEID"
+    )
+    parsed = parse_encrypted_content(content.to_dict())
+    assert parsed.sender_key == sender_key
+    assert parsed.device_id == "DEVICEID"
+
+@pytest.mark.parametrize(
+    "content",
+    (
+        {"algorithm": "m.unknown"},
+        {"ciphertext": "AAAA"},
+        {"algorithm": "m.megolm.v1.aes-sha2", "session_id": "ID"},
+        {"algorithm": "m.megolm.v1.aes-sha2", "ciphertext": "AAAA", "session_id": "ID"},
+        {"algorithm": "m.olm.v1.curve25519-aes-sha2", "sender_key": "AAAA", "ciphertext": {}},
+        {
+            "algorithm": "m.olm.v1.curve25519-aes-sha2",
+            "sender_key": Account().curve25519_key.to_base64(),
+            "ciphertext": {"KEY": {"type": 2, "body": "AAAA"}},
+        },
+    ),
+)
+def test_invalid_content(content: dict):
+    with pytest.raises(EncryptedContentException):
+        parse_encrypted_content(content)
+
+def test_invalid_recipients():
+    alice, bob = Account(), Account()
+    bob.generate_one_time_keys(1)
+    one_time_key = next(iter(bob.one_time_keys.values()))
+    message = alice.create_outbound_session(bob.curve25519_key, one_time_key).encrypt(b"Hello")
+
+    with pytest.raises(EncryptedContentException):
+        OlmEncryptedContent(alice.curve25519_key, {"not a key": message})
+
+    content = OlmEncryptedContent(alice.curve25519_key, {bob.curve25519_key.to_base64(): message})
+    content_dict = content.to_dict()
+
+    with pytest.raises(EncryptedContentException, match="Invalid ciphertext entry for KEY"):
+        parse_encrypted_content({**content_dict, "ciphertext": {"KEY": "AAAA"}})
+
+    recipient_key = bob.curve25519_key.to_base64()
+    ciphertext = {"not a key": content_dict["ciphertext"][recipient_key]}
+
+    with pytest.raises(EncryptedContentException):
+        parse_encrypted_content({**content_dict, "ciphertext": ciphertext})
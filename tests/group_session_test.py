@@ -4,7 +4,11 @@ from vodozemac import (
     GroupSession,
     InboundGroupSession,
     MegolmDecryptionException,
+    MegolmMessage,
+    MegolmSessionConfig,
     PickleException,
+    SessionOrdering,
+    SignatureException,
 )
 
 @pytest.fixture(scope="module")
@@ -87,3 +91,146 @@ def test_inbound_clear():
     outbound = GroupSession()
     inbound = InboundGroupSession(outbound.session_key)
     del inbound
+
+
+def test_default_config(group_session: GroupSession, inbound_group_session: InboundGroupSession):
+    assert group_session.session_config == MegolmSessionConfig.version_1()
+    assert inbound_group_session.session_config == MegolmSessionConfig.version_1()
+
+def test_version_2_session(pickle_key: bytes):
+    outbound = GroupSession(MegolmSessionConfig.version_2())
+    inbound = InboundGroupSession(outbound.session_key, MegolmSessionConfig.version_2())
+
+    assert outbound.session_config.version == 2
+    assert inbound.session_config.version == 2
+
+    assert inbound.decrypt(outbound.encrypt(b"Test")).plaintext == b"Test"
+
+    # The config of a pickled session isn't known, but it's still used.
+    unpickled = InboundGroupSession.from_pickle(inbound.pickle(pickle_key), pickle_key)
+    assert unpickled.session_config is None
+
+    outbound = GroupSession.from_pickle(outbound.pickle(pickle_key), pickle_key)
+    assert outbound.session_config == MegolmSessionConfig.version_2()
+
+    imported = InboundGroupSession.import_session(
+        inbound.export_at(0), MegolmSessionConfig.version_2()
+    )
+    assert imported.session_config == MegolmSessionConfig.version_2()
+    assert imported.decrypt(outbound.encrypt(b"Test")).plaintext == b"Test"
+    assert unpickled.decrypt(outbound.encrypt(b"Test")).plaintext == b"Test"
+
+    merged = unpickled.merge(imported)
+    assert merged is not None
+    assert merged.session_config == MegolmSessionConfig.version_2()
+
+@pytest.mark.parametrize(
+    "outbound_config, inbound_config",
+    (
+        (MegolmSessionConfig.version_1(), MegolmSessionConfig.version_2()),
+        (MegolmSessionConfig.version_2(), MegolmSessionConfig.version_1()),
+    ),
+)
+def test_mismatched_config(outbound_config: MegolmSessionConfig, inbound_config: MegolmSessionConfig):
+    outbound = GroupSession(outbound_config)
+    inbound = InboundGroupSession(outbound.session_key, inbound_config)
+
+    with pytest.raises(MegolmDecryptionException):
+        inbound.decrypt(outbound.encrypt(b"Test"))
+
+def test_message_fields():
+    outbound = GroupSession()
+    inbound = InboundGroupSession(outbound.session_key)
+
+    outbound.encrypt(b"First")
+    message = MegolmMessage.from_bytes(outbound.encrypt(b"Second").to_bytes())
+
+    assert message.message_index() == 1
+    assert len(message.mac()) == 8
+    assert len(message.ciphertext()) > 0
+    assert message.to_bytes()[-72:-64] == message.mac()
+
+    assert inbound.signing_key.to_base64() == inbound.session_id
+    message.verify_signature(inbound.signing_key)
+
+    other = InboundGroupSession(GroupSession().session_key)
+    with pytest.raises(SignatureException):
+        message.verify_signature(other.signing_key)
+
+    version_2 = GroupSession(MegolmSessionConfig.version_2())
+    assert len(version_2.encrypt(b"Test").mac()) == 32
+
+def test_session_ordering():
+    outbound = GroupSession()
+    first = InboundGroupSession(outbound.session_key)
+    second = InboundGroupSession.import_session(first.export_at(10))
+    unconnected = InboundGroupSession(GroupSession().session_key)
+
+    assert first.compare(first) == SessionOrdering.Equal
+    assert first.compare(InboundGroupSession(outbound.session_key)) == SessionOrdering.Equal
+    assert first.compare(second) == SessionOrdering.Better
+    assert second.compare(first) == SessionOrdering.Worse
+    assert first.compare(unconnected) == SessionOrdering.Unconnected
+
+    assert first.connected(second)
+    assert not first.connected(unconnected)
+
+def test_session_merge():
+    outbound = GroupSession()
+    first = InboundGroupSession(outbound.session_key)
+    second = InboundGroupSession.import_session(first.export_at(10))
+
+    merged = second.merge(first)
+    assert merged is not None
+    assert merged.first_known_index == 0
+    assert merged.session_id == first.session_id
+    assert merged.compare(first) == SessionOrdering.Equal
+    assert merged.compare(second) == SessionOrdering.Better
+    assert merged.decrypt(outbound.encrypt(b"Test")).plaintext == b"Test"
+
+    assert first.merge(InboundGroupSession(GroupSession().session_key)) is None
+
+def test_advance_to():
+    outbound = GroupSession()
+    inbound = InboundGroupSession(outbound.session_key)
+
+    messages = [outbound.encrypt(b"Test") for _ in range(3)]
+    assert inbound.highest_decrypted_index is None
+
+    assert inbound.decrypt(messages[1]).message_index == 1
+    assert inbound.highest_decrypted_index == 1
+    inbound.decrypt(messages[0])
+    assert inbound.highest_decrypted_index == 1
+
+    assert inbound.has_ratchet_at(0)
+    assert inbound.advance_to(2)
+    assert inbound.first_known_index == 2
+    assert not inbound.has_ratchet_at(1)
+    assert inbound.has_ratchet_at(2)
+    assert not inbound.advance_to(1)
+
+    with pytest.raises(MegolmDecryptionException):
+        inbound.decrypt(messages[0])
+
+    assert inbound.decrypt(messages[2]).plaintext == b"Test"
+    assert inbound.highest_decrypted_index == 2
+
+def test_pickle_highest_decrypted_index(pickle_key: bytes):
+    outbound = GroupSession()
+    inbound = InboundGroupSession(outbound.session_key)
+
+    outbound.encrypt(b"Test")
+    inbound.decrypt(outbound.encrypt(b"Test"))
+
+    # The pickle is a plain vodozemac pickle, the index is stored separately.
+    pickle = inbound.pickle(pickle_key)
+    unpickled = InboundGroupSession.from_pickle(pickle, pickle_key)
+    assert unpickled.session_id == inbound.session_id
+    assert unpickled.highest_decrypted_index is None
+
+    unpickled = InboundGroupSession.from_pickle(
+        pickle, pickle_key, inbound.highest_decrypted_index
+    )
+    assert unpickled.highest_decrypted_index == 1
+    assert unpickled.decrypt(outbound.encrypt(b"Test")).message_index == 2
+    assert unpickled.highest_decrypted_index == 2
@@ -0,0 +1,85 @@
+import hashlib
+import hmac
+import json
+from base64 import b64decode, b64encode
+
+import pytest
+from hypothesis import given
+from vodozemac import (
+    GroupSession,
+    InboundGroupSession,
+    KeyExportException,
+    decrypt_key_export,
+    encrypt_key_export,
+)
+
+PASSPHRASE = "It's a secret to everybody"
+
+# Test vector from Element's MegolmExportEncryption tests.
+ELEMENT_EXPORT = (
+    "-----BEGIN MEGOLM SESSION DATA-----\n"
+    "AXNhbHRzYWx0c2FsdHNhbHSIiIiIiIiIiIiIiIiIiIiIAAAACmIRUW2OjZ3L2l6j9h0lHlV3M2dx\n"
+    "cissyYBxjsfsAndErh065A8=\n"
+    "-----END MEGOLM SESSION DATA-----"
+)
+
+def test_element_test_vector():
+    assert decrypt_key_export(ELEMENT_EXPORT, "password") == "plain"
+
+def test_export_format():
+    export = encrypt_key_export("[]", PASSPHRASE, 10)
+    lines = export.splitlines()
+
+    assert lines[0] == "-----BEGIN MEGOLM SESSION DATA-----"
+    assert lines[-1] == "-----END MEGOLM SESSION DATA-----"
+
+    payload = b64decode("".join(lines[1:-1]))
+    salt, rounds = payload[1:17], int.from_bytes(payload[33:37], "big")
+
+    assert payload[0] == 1
+    assert rounds == 10
+
+    keys = hashlib.pbkdf2_hmac("sha512", PASSPHRASE.encode(), salt, rounds, 64)
+    mac = hmac.new(keys[32:], payload[:-32], hashlib.sha256).digest()
+
+    assert mac == payload[-32:]
+
+@given(plaintext=...)
+def test_encrypt_decrypt(plaintext: str):
+    export = encrypt_key_export(plaintext, PASSPHRASE, 10)
+    assert decrypt_key_export(export, PASSPHRASE) == plaintext
+
+def test_exported_session_roundtrip():
+    session = GroupSession()
+    exported = InboundGroupSession(session.session_key).export_at(0)
+    sessions = json.dumps([{"session_key": exported.to_base64()}])
+
+    export = encrypt_key_export(sessions, PASSPHRASE, 10)
+
+    assert json.loads(decrypt_key_export(export, PASSPHRASE)) == json.loads(sessions)
+
+def test_invalid_exports():
+    export = encrypt_key_export("[]", PASSPHRASE, 10)
+
+    with pytest.raises(KeyExportException, match="MAC"):
+        decrypt_key_export(export, "wrong passphrase")
+
+    with pytest.raises(KeyExportException, match="header"):
+        decrypt_key_export(export.replace("BEGIN", "START"), PASSPHRASE)
+
+    with pytest.raises(KeyExportException):
+        encrypt_key_export("[]", PASSPHRASE, 0)
+
+    with pytest.raises(KeyExportException, match="rounds"):
+        encrypt_key_export("[]", PASSPHRASE, 2**32 - 1)
+
+def test_too_many_rounds():
+    lines = encrypt_key_export("[]", PASSPHRASE, 10).splitlines()
+    payload = bytearray(b64decode("".join(lines[1:-1])))
+    payload[33:37] = (2**32 - 1).to_bytes(4, "big")
+
+    export = "\n".join([lines[0], b64encode(payload).decode(), lines[-1]])
+
+    # The rounds are checked before the key derivation, so this fails quickly.
+    with pytest.raises(KeyExportException, match="rounds"):
+        decrypt_key_export(export, PASSPHRASE)
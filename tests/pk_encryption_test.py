@@ -1,3 +1,4 @@
+import json
 import pytest
 from base64 import b64encode
 from hypothesis import given
@@ -5,6 +6,8 @@ from vodozemac import (
     Message,
     Curve25519PublicKey,
     Curve25519SecretKey,
+    GroupSession,
+    InboundGroupSession,
     PkDecodeException,
     PkDecryption,
     PkEncryption,
@@ -80,3 +83,53 @@ def test_message_from_invalid_base64():
             b64encode(b"some_mac").decode(),
             "not-valid-base64!@#"  # Invalid base64 for ephemeral key
         )
+
+@given(cleartext=...)
+def test_message_base64_roundtrip(pk_decryption: PkDecryption, pk_encryption: PkEncryption, cleartext: bytes):
+    message = Message.from_base64(*pk_encryption.encrypt(cleartext).to_base64())
+    assert cleartext == pk_decryption.decrypt(message)
+
+def test_backup_session_roundtrip(pk_decryption: PkDecryption, pk_encryption: PkEncryption):
+    outbound = GroupSession()
+    session = InboundGroupSession(outbound.session_key)
+    sender_key = Curve25519SecretKey().public_key()
+    forwarding_chain = [Curve25519SecretKey().public_key().to_base64()]
+    claimed_keys = {"ed25519": "+nYxYhkyQRxfYP1KyHbWQeZd7WWSqcQvMdsS3dXTuSM"}
+
+    session_data = pk_encryption.encrypt_backup_session(
+        session, sender_key, forwarding_chain, claimed_keys
+    )
+    assert set(session_data) == {"ephemeral", "ciphertext", "mac"}
+
+    decrypted, data = pk_decryption.decrypt_backup_session(json.dumps(session_data))
+
+    assert decrypted.session_id == session.session_id
+    assert decrypted.first_known_index == 0
+    assert data["algorithm"] == "m.megolm.v1.aes-sha2"
+    assert data["sender_key"] == sender_key.to_base64()
+    assert data["sender_claimed_keys"] == claimed_keys
+    assert data["forwarding_curve25519_key_chain"] == forwarding_chain
+
+    message = outbound.encrypt(b"It's a secret to everybody")
+    assert decrypted.decrypt(message).plaintext == b"It's a secret to everybody"
+
+def test_invalid_backup_session(pk_decryption: PkDecryption, pk_encryption: PkEncryption):
+    with pytest.raises(PkDecodeException, match="ephemeral"):
+        pk_decryption.decrypt_backup_session({"ciphertext": "", "mac": ""})
+
+    ciphertext, mac, ephemeral = pk_encryption.encrypt(
+        json.dumps({"algorithm": "m.megolm.v2.aes-sha2"}).encode()
+    ).to_base64()
+    session_data = {"ciphertext": ciphertext, "mac": mac, "ephemeral": ephemeral}
+
+    with pytest.raises(PkDecodeException, match="m.megolm.v2.aes-sha2"):
+        pk_decryption.decrypt_backup_session(session_data)
+
+    with pytest.raises(PkDecodeException, match="JSON"):
+        pk_decryption.decrypt_backup_session("not json")
+
+    ciphertext, mac, ephemeral = pk_encryption.encrypt(b"[]").to_base64()
+    session_data = {"ciphertext": ciphertext, "mac": mac, "ephemeral": ephemeral}
+
+    with pytest.raises(PkDecodeException, match="JSON"):
+        pk_decryption.decrypt_backup_session(session_data)
@@ -1,7 +1,7 @@
 from typing import Final
 
 import pytest
-from vodozemac import Sas, EstablishedSas
+from vodozemac import Sas, EstablishedSas, SasVerification, SasException
 from vodozemac.vodozemac import Curve25519PublicKey
 
 EXTRA_INFO: Final[str] = "extra_info"
@@ -42,3 +42,187 @@ def test_mac_generating(alice_established_sas: EstablishedSas, bob_established_s
     assert bob_established_sas.verify_mac(message, EXTRA_INFO, alice_mac) is None
 
     assert alice_mac == bob_mac
+
+ALICE_KEYS = {"ed25519:ALICEDEVICE": Sas().public_key.to_base64()}
+BOB_KEYS = {"ed25519:BOBDEVICE": Sas().public_key.to_base64()}
+
+def create_verifications(transaction_id: str = "txn") -> tuple[SasVerification, SasVerification]:
+    alice = SasVerification(
+        "@alice:example.org", "ALICEDEVICE", ALICE_KEYS,
+        "@bob:example.org", "BOBDEVICE", BOB_KEYS,
+        transaction_id,
+    )
+    bob = SasVerification(
+        "@bob:example.org", "BOBDEVICE", BOB_KEYS,
+        "@alice:example.org", "ALICEDEVICE", ALICE_KEYS,
+        transaction_id,
+    )
+    return alice, bob
+
+def deliver(events, receiver: SasVerification):
+    responses = []
+    for event_type, content in events:
+        responses.extend(receiver.receive_event(event_type, content))
+    return responses
+
+def test_sas_verification():
+    alice, bob = create_verifications()
+
+    assert deliver(alice.start(), bob) == []
+    assert bob.state == "started"
+
+    [(event_type, key)] = deliver(bob.accept(), alice)
+    assert event_type == "m.key.verification.key"
+
+    [(event_type, _)] = deliver([(event_type, key)], bob)
+    assert event_type == "m.key.verification.key"
+    assert deliver([(event_type, _)], alice) == []
+
+    assert alice.state == bob.state == "keys_exchanged"
+    assert alice.decimals is not None
+    assert alice.decimals == bob.decimals
+    assert alice.emoji_indices == bob.emoji_indices
+    assert alice.short_authentication_string == ["decimal", "emoji"]
+
+    alice_mac = alice.confirm()
+    assert [event_type for event_type, _ in alice_mac] == ["m.key.verification.mac"]
+
+    bob_events = bob.confirm() + deliver(alice_mac, bob)
+    assert [event_type for event_type, _ in bob_events] == [
+        "m.key.verification.mac",
+        "m.key.verification.done",
+    ]
+    assert bob.verified_keys == ["ed25519:ALICEDEVICE"]
+
+    [(event_type, done)] = deliver(bob_events, alice)
+    assert event_type == "m.key.verification.done"
+    assert alice.is_verified
+    assert alice.verified_keys == ["ed25519:BOBDEVICE"]
+
+    assert deliver([(event_type, done)], bob) == []
+    assert bob.is_verified
+    assert bob.state == "verified"
+
+def test_sas_verification_legacy_methods():
+    alice = Sas()
+    bob = SasVerification(
+        "@bob:example.org", "BOBDEVICE", BOB_KEYS,
+        "@alice:example.org", "ALICEDEVICE", ALICE_KEYS,
+        "txn",
+    )
+
+    start = {
+        "from_device": "ALICEDEVICE",
+        "method": "m.sas.v1",
+        "transaction_id": "txn",
+        "key_agreement_protocols": ["curve25519"],
+        "hashes": ["sha256"],
+        "message_authentication_codes": ["hkdf-hmac-sha256"],
+        "short_authentication_string": ["decimal"],
+    }
+    bob.receive_event("m.key.verification.start", start)
+
+    [(_, accept)] = bob.accept()
+    assert accept["key_agreement_protocol"] == "curve25519"
+    assert accept["message_authentication_code"] == "hkdf-hmac-sha256"
+    assert accept["short_authentication_string"] == ["decimal"]
+
+    key = {"transaction_id": "txn", "key": alice.public_key.to_base64()}
+    [(_, bob_key)] = bob.receive_event("m.key.verification.key", key)
+    alice = alice.diffie_hellman(Curve25519PublicKey.from_base64(bob_key["key"]))
+
+    info = "MATRIX_KEY_VERIFICATION_SAS@alice:example.orgALICEDEVICE@bob:example.orgBOBDEVICEtxn"
+    assert bob.decimals == alice.bytes(info).decimals
+    assert bob.emoji_indices is None
+
+    info = "MATRIX_KEY_VERIFICATION_MAC@alice:example.orgALICEDEVICE@bob:example.orgBOBDEVICEtxn"
+    [(key_id, alice_key)] = ALICE_KEYS.items()
+    mac = {
+        "transaction_id": "txn",
+        "mac": {key_id: alice.calculate_mac_invalid_base64(alice_key, info + key_id)},
+        "keys": alice.calculate_mac_invalid_base64(key_id, info + "KEY_IDS"),
+    }
+    bob.receive_event("m.key.verification.mac", mac)
+
+    assert not bob.is_cancelled
+    assert bob.verified_keys == [key_id]
+
+def test_sas_verification_mismatched_commitment():
+    alice, bob = create_verifications()
+
+    deliver(alice.start(), bob)
+    [(event_type, accept)] = bob.accept()
+    accept["commitment"] = "invalid"
+
+    [(_, key)] = alice.receive_event(event_type, accept)
+    [(event_type, cancel)] = deliver(bob.receive_event("m.key.verification.key", key), alice)
+
+    assert event_type == "m.key.verification.cancel"
+    assert cancel["code"] == "m.mismatched_commitment"
+    assert alice.is_cancelled
+    assert alice.cancel_code == "m.mismatched_commitment"
+
+def test_sas_verification_key_mismatch():
+    alice, _ = create_verifications()
+    bob = SasVerification(
+        "@bob:example.org", "BOBDEVICE", BOB_KEYS,
+        "@alice:example.org", "ALICEDEVICE", {"ed25519:ALICEDEVICE": Sas().public_key.to_base64()},
+        "txn",
+    )
+
+    deliver(alice.start(), bob)
+    deliver(deliver(deliver(bob.accept(), alice), bob), alice)
+
+    [(event_type, cancel)] = deliver(alice.confirm(), bob)
+
+    assert event_type == "m.key.verification.cancel"
+    assert cancel["code"] == "m.key_mismatch"
+    assert bob.is_cancelled
+
+def test_sas_verification_cancellation():
+    alice, bob = create_verifications()
+
+    [(event_type, cancel)] = alice.receive_event(
+        "m.key.verification.key", {"transaction_id": "txn", "key": ""}
+    )
+    assert event_type == "m.key.verification.cancel"
+    assert cancel["code"] == "m.unexpected_message"
+    assert alice.state == "cancelled"
+
+    assert deliver([(event_type, cancel)], bob) == []
+    assert bob.cancel_code == "m.unexpected_message"
+    assert bob.is_cancelled
+
+    with pytest.raises(SasException):
+        bob.cancel()
+
+def test_sas_verification_simultaneous_start():
+    alice, bob = create_verifications()
+
+    alice_start = alice.start()
+    bob_start = bob.start()
+
+    # Alice has the lower user ID, so her start event wins and Bob accepts it.
+    assert deliver(bob_start, alice) == []
+    assert alice.we_started
+    assert alice.state == "started"
+
+    assert deliver(alice_start, bob) == []
+    assert not bob.we_started
+    assert bob.state == "started"
+
+    [(event_type, key)] = deliver(bob.accept(), alice)
+    assert deliver(deliver([(event_type, key)], bob), alice) == []
+    assert alice.state == bob.state == "keys_exchanged"
+    assert alice.decimals == bob.decimals
+
+def test_sas_verification_transaction_mismatch():
+    alice, bob = create_verifications()
+    _, other_bob = create_verifications("other_txn")
+
+    [(event_type, start)] = alice.start()
+
+    with pytest.raises(SasException, match="other_txn"):
+        other_bob.receive_event(event_type, start)
+
+    assert other_bob.state == "created"
@@ -0,0 +1,95 @@
+from base64 import b64decode
+
+import pytest
+from hypothesis import given
+from vodozemac import (
+    Curve25519SecretKey,
+    RecoveryKeyException,
+    SecretStorageException,
+    SecretStorageKey,
+)
+
+SECRET_NAME = "m.cross_signing.master"
+
+# Encrypted with the key derived from the "password" passphrase, using the
+# "salt" salt and 10 iterations, and an all-zero IV.
+ENCRYPTED_SECRET = {
+    "iv": "AAAAAAAAAAAAAAAAAAAAAA==",
+    "ciphertext": "vMKpIHYKYpdAQO927uF7mzUXHIKTBz0vqXQ=",
+    "mac": "hHX2xxRztIBVyY4jo1pbxqC6u5lRG7ZH2dK8SFWNEGg=",
+}
+
+# The key check of the same key, using an all-zero IV.
+KEY_DESCRIPTION = {
+    "algorithm": "m.secret_storage.v1.aes-hmac-sha2",
+    "passphrase": {"algorithm": "m.pbkdf2", "salt": "salt", "iterations": 10},
+    "iv": "AAAAAAAAAAAAAAAAAAAAAA",
+    "mac": "Dh3ngXB/Bj62dgwbEzhwM9b96ud/ix9Z4iDvKJp0PXM",
+}
+
+
+@pytest.fixture(scope="module")
+def key() -> SecretStorageKey:
+    return SecretStorageKey.from_passphrase("password", "salt", 10)
+
+def test_decrypt_test_vector(key: SecretStorageKey):
+    assert key.decrypt_secret(SECRET_NAME, ENCRYPTED_SECRET) == "It's a secret to everybody"
+
+    with pytest.raises(SecretStorageException, match="MAC"):
+        key.decrypt_secret("m.cross_signing.self_signing", ENCRYPTED_SECRET)
+
+@given(plaintext=...)
+def test_encrypt_decrypt(key: SecretStorageKey, plaintext: str):
+    encrypted = key.encrypt_secret(SECRET_NAME, plaintext)
+
+    assert set(encrypted) == {"iv", "ciphertext", "mac"}
+    assert key.decrypt_secret(SECRET_NAME, encrypted) == plaintext
+
+def test_check_key(key: SecretStorageKey):
+    assert key.verify_check_key(KEY_DESCRIPTION)
+    assert not SecretStorageKey().verify_check_key(KEY_DESCRIPTION)
+
+    check = key.check_key()
+
+    assert set(check) == {"iv", "mac"}
+    assert b64decode(check["iv"] + "==")[8] & 0x80 == 0
+    assert key.verify_check_key(check)
+    assert not SecretStorageKey().verify_check_key(check)
+
+def test_recovery_key():
+    key = SecretStorageKey()
+    check = key.check_key()
+
+    restored = SecretStorageKey.from_recovery_key(key.to_recovery_key())
+    assert restored.verify_check_key(check)
+
+    with pytest.raises(RecoveryKeyException):
+        SecretStorageKey.from_recovery_key("not a recovery key")
+
+def test_backup_key_in_secret_storage(key: SecretStorageKey):
+    backup_key = Curve25519SecretKey()
+    encrypted = key.encrypt_secret("m.megolm_backup.v1", backup_key.to_base64())
+
+    decrypted = Curve25519SecretKey.from_base64(key.decrypt_secret("m.megolm_backup.v1", encrypted))
+    assert decrypted.to_bytes() == backup_key.to_bytes()
+
+def test_invalid_encrypted_secret(key: SecretStorageKey):
+    with pytest.raises(SecretStorageException, match="ciphertext"):
+        key.decrypt_secret(SECRET_NAME, {"iv": ENCRYPTED_SECRET["iv"], "mac": ENCRYPTED_SECRET["mac"]})
+
+    with pytest.raises(SecretStorageException, match="IV"):
+        key.decrypt_secret(SECRET_NAME, {**ENCRYPTED_SECRET, "iv": "AAAA"})
+
+    with pytest.raises(SecretStorageException, match="JSON"):
+        key.decrypt_secret(SECRET_NAME, "not json")
+
+    with pytest.raises(SecretStorageException, match="JSON"):
+        key.verify_check_key("[]")
+
+    with pytest.raises(SecretStorageException):
+        SecretStorageKey.from_passphrase("password", "salt", 0)
+
+    # The iterations come from the key description, a huge number is rejected
+    # before the key derivation.
+    with pytest.raises(SecretStorageException, match="rounds"):
+        SecretStorageKey.from_passphrase("password", "salt", 2**32 - 1)
@@ -0,0 +1,166 @@
+import pytest
+from vodozemac import (
+    Account,
+    OlmDecryptionException,
+    OlmSessionStore,
+    PickleException,
+)
+
+
+def one_time_key(account: Account):
+    account.generate_one_time_keys(1)
+    key = next(iter(account.one_time_keys.values()))
+    account.mark_keys_as_published()
+
+    return key
+
+
+def test_routing():
+    alice, bob = Account(), Account()
+    alice_store, bob_store = OlmSessionStore(), OlmSessionStore()
+
+    assert alice_store.encrypt(bob.curve25519_key, b"Hello") is None
+
+    session_id = alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key(bob))
+    assert alice_store.session_ids(bob.curve25519_key) == [session_id]
+
+    message = alice_store.encrypt(bob.curve25519_key, b"Hello")
+    result = bob_store.decrypt(bob, alice.curve25519_key, message)
+    assert result.plaintext == b"Hello"
+    assert result.session_id == session_id
+    assert result.created_session
+    assert bob_store.session_ids(alice.curve25519_key) == [session_id]
+
+    # A second pre-key message is routed to the existing session.
+    message = alice_store.encrypt(bob.curve25519_key, b"Hello again")
+    result = bob_store.decrypt(bob, alice.curve25519_key, message)
+    assert result.plaintext == b"Hello again"
+    assert not result.created_session
+    assert result.used_key_id is None
+    assert len(bob_store) == 1
+
+    reply = bob_store.encrypt(alice.curve25519_key, b"Hi")
+    assert alice_store.decrypt(alice, bob.curve25519_key, reply).plaintext == b"Hi"
+
+    message = alice_store.encrypt(bob.curve25519_key, b"Normal")
+    assert message.to_normal() is not None
+    assert bob_store.decrypt(bob, alice.curve25519_key, message).plaintext == b"Normal"
+
+def test_used_keys():
+    alice, bob = Account(), Account()
+    alice_store, bob_store = OlmSessionStore(), OlmSessionStore()
+
+    bob.generate_one_time_keys(1)
+    key_id, one_time_key = next(iter(bob.one_time_keys.items()))
+    bob.generate_fallback_key()
+    fallback_key_id, fallback_key = next(iter(bob.fallback_key.items()))
+
+    alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key)
+    result = bob_store.decrypt(bob, alice.curve25519_key, alice_store.encrypt(bob.curve25519_key, b"Hi"))
+    assert result.used_key_id == key_id
+    assert not result.used_fallback_key
+
+    alice_store.create_outbound_session(alice, bob.curve25519_key, fallback_key)
+    result = bob_store.decrypt(bob, alice.curve25519_key, alice_store.encrypt(bob.curve25519_key, b"Hi"))
+    assert result.created_session
+    assert result.used_key_id == fallback_key_id
+    assert result.used_fallback_key
+
+def test_add_get_remove_sessions():
+    alice, bob = Account(), Account()
+    store = OlmSessionStore()
+
+    session = alice.create_outbound_session(bob.curve25519_key, one_time_key(bob))
+    store.add_session(bob.curve25519_key, session)
+    store.add_session(bob.curve25519_key, session)
+    assert store.session_ids(bob.curve25519_key) == [session.session_id]
+
+    sessions = store.get_sessions(bob.curve25519_key)
+    assert [s.session_id for s in sessions] == [session.session_id]
+    assert store.get_sessions(alice.curve25519_key) == []
+
+    message = store.encrypt(bob.curve25519_key, b"Hello")
+    _, plaintext = bob.create_inbound_session(alice.curve25519_key, message.to_pre_key())
+    assert plaintext == b"Hello"
+
+    removed = store.remove_sessions(bob.curve25519_key)
+    assert [s.session_id for s in removed] == [session.session_id]
+    assert len(store) == 0
+    assert store.remove_sessions(bob.curve25519_key) == []
+
+def test_last_use_ordering():
+    alice, bob = Account(), Account()
+    alice_store, bob_store = OlmSessionStore(), OlmSessionStore()
+
+    first = alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key(bob))
+    second = alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key(bob))
+    assert alice_store.session_ids(bob.curve25519_key) == [second, first]
+
+    first_session = alice.create_outbound_session(bob.curve25519_key, one_time_key(bob))
+    second_session = alice.create_outbound_session(bob.curve25519_key, one_time_key(bob))
+
+    bob_store.decrypt(bob, alice.curve25519_key, first_session.encrypt(b"First"))
+    bob_store.decrypt(bob, alice.curve25519_key, second_session.encrypt(b"Second"))
+    assert bob_store.session_ids(alice.curve25519_key) == [
+        second_session.session_id, first_session.session_id
+    ]
+
+    assert bob_store.decrypt(bob, alice.curve25519_key, first_session.encrypt(b"Again")).plaintext == b"Again"
+    assert bob_store.session_ids(alice.curve25519_key) == [
+        first_session.session_id, second_session.session_id
+    ]
+
+def test_session_cap():
+    alice, bob = Account(), Account()
+    alice_store = OlmSessionStore(max_sessions_per_device=2)
+    assert alice_store.max_sessions_per_device == 2
+
+    session_ids = [
+        alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key(bob))
+        for _ in range(3)
+    ]
+
+    assert alice_store.session_ids(bob.curve25519_key) == session_ids[:0:-1]
+    assert len(alice_store) == 2
+
+def test_undecryptable_message():
+    alice, bob, eve = Account(), Account(), Account()
+    alice_store, bob_store = OlmSessionStore(), OlmSessionStore()
+
+    alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key(bob))
+    bob_store.decrypt(bob, alice.curve25519_key, alice_store.encrypt(bob.curve25519_key, b"Hello"))
+
+    eve_store = OlmSessionStore()
+    eve_store.create_outbound_session(eve, bob.curve25519_key, one_time_key(bob))
+    message = eve_store.encrypt(bob.curve25519_key, b"Hello").to_pre_key().message.to_any()
+
+    with pytest.raises(OlmDecryptionException):
+        bob_store.decrypt(bob, alice.curve25519_key, message)
+
+    with pytest.raises(OlmDecryptionException):
+        bob_store.decrypt(bob, eve.curve25519_key, message)
+
+def test_pickle(pickle_key: bytes):
+    alice, bob = Account(), Account()
+    alice_store, bob_store = OlmSessionStore(max_sessions_per_device=5), OlmSessionStore()
+
+    alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key(bob))
+    second = alice_store.create_outbound_session(alice, bob.curve25519_key, one_time_key(bob))
+
+    # The identity keys of the devices aren't readable from the pickle.
+    pickle = alice_store.pickle(pickle_key)
+    assert bob.curve25519_key.to_base64() not in pickle
+
+    unpickled = OlmSessionStore.from_pickle(pickle, pickle_key)
+    assert unpickled.max_sessions_per_device == 5
+    assert unpickled.session_ids(bob.curve25519_key) == alice_store.session_ids(bob.curve25519_key)
+
+    message = unpickled.encrypt(bob.curve25519_key, b"Hello")
+    assert bob_store.decrypt(bob, alice.curve25519_key, message).plaintext == b"Hello"
+    assert bob_store.session_ids(alice.curve25519_key) == [second]
+
+    with pytest.raises(PickleException):
+        OlmSessionStore.from_pickle(alice_store.pickle(pickle_key), b"WRONG_PICKLE_KEY_1234567890_____")
+
+    with pytest.raises(PickleException):
+        OlmSessionStore.from_pickle("not json", pickle_key)
@@ -1,3 +1,4 @@
+import json
 from collections.abc import Generator
 from typing import TypeAlias
 
@@ -6,9 +7,14 @@ from vodozemac import (
     Account,
     AnyOlmMessage,
     DecodeException,
+    OlmSessionConfig,
     Session,
     PickleException,
-    PreKeyMessage
+    PreKeyMessage,
+    NormalOlmMessage,
+    SessionKeys,
+    encrypt_for_devices,
+    parse_encrypted_content,
 )
 
 
@@ -115,3 +121,180 @@ def test_message_to_parts(alice: Account, bob: Account, alice_session: Session):
 
     bob_session, alice_decrypted = bob.create_inbound_session(alice.curve25519_key, alice_message)
     assert alice_plaintext == alice_decrypted
+
+def test_pre_key_message_fields(alice: Account, bob: Account):
+    bob.generate_one_time_keys(1)
+    one_time_key = next(iter(bob.one_time_keys.values()))
+    alice_session = alice.create_outbound_session(bob.curve25519_key, one_time_key)
+
+    message = alice_session.encrypt(b"It's a secret to everybody").to_pre_key()
+
+    assert message.identity_key == alice.curve25519_key
+    assert message.one_time_key == one_time_key
+    assert message.base_key != alice.curve25519_key
+    assert message.version == 3
+    assert isinstance(message.message, NormalOlmMessage)
+    assert message.message.version == message.version
+    assert message.message.to_any().to_parts()[0] == 1
+
+    from_bytes = PreKeyMessage.from_bytes(message.to_bytes())
+    from_base64 = PreKeyMessage.from_base64(message.to_base64())
+
+    assert from_bytes.session_id() == from_base64.session_id() == message.session_id()
+    assert from_bytes.to_bytes() == message.to_bytes()
+
+    with pytest.raises(DecodeException):
+        PreKeyMessage.from_bytes(b"")
+
+def test_normal_message_fields(alice: Account, bob: Account):
+    bob.generate_one_time_keys(1)
+    one_time_key = next(iter(bob.one_time_keys.values()))
+    alice_session = alice.create_outbound_session(bob.curve25519_key, one_time_key)
+
+    pre_key = alice_session.encrypt(b"It's a secret to everybody").to_pre_key()
+    assert pre_key.message.to_any().to_normal() is not None
+    assert pre_key.to_any().to_normal() is None
+
+    bob_session, _ = bob.create_inbound_session(alice.curve25519_key, pre_key)
+    bob_session.encrypt(b"Hello")
+    message = bob_session.encrypt(b"Hello again").to_normal()
+
+    assert message.chain_index == 1
+    assert message.version == 3
+    assert message.mac_truncated
+    assert len(message.mac) == 8
+    assert message.to_bytes().endswith(message.mac)
+    assert message.ratchet_key != bob.curve25519_key
+    assert len(message.ciphertext) > 0
+    assert "chain_index=1" in repr(message)
+
+    from_bytes = NormalOlmMessage.from_bytes(message.to_bytes())
+    from_base64 = NormalOlmMessage.from_base64(message.to_base64())
+
+    assert from_bytes.to_bytes() == from_base64.to_bytes() == message.to_bytes()
+    assert from_bytes.ratchet_key == message.ratchet_key
+    assert alice_session.decrypt(from_bytes.to_any()) == b"Hello again"
+
+    with pytest.raises(DecodeException):
+        NormalOlmMessage.from_bytes(b"")
+
+def test_session_metadata(alice: Account, bob: Account):
+    bob.generate_one_time_keys(1)
+    one_time_key = next(iter(bob.one_time_keys.values()))
+    alice_session = alice.create_outbound_session(bob.curve25519_key, one_time_key)
+
+    message = alice_session.encrypt(b"It's a secret to everybody").to_pre_key()
+    bob_session, _ = bob.create_inbound_session(alice.curve25519_key, message)
+
+    session_keys = alice_session.session_keys()
+    assert isinstance(session_keys, SessionKeys)
+    assert session_keys == bob_session.session_keys() == message.session_keys()
+    assert session_keys.identity_key == alice.curve25519_key
+    assert session_keys.one_time_key == one_time_key
+    assert session_keys.base_key == message.base_key
+    assert session_keys.session_id() == alice_session.session_id
+
+    assert not alice_session.has_received_message
+    assert bob_session.has_received_message
+
+    alice_session.decrypt(bob_session.encrypt(b"Hello"))
+    assert alice_session.has_received_message
+
+    description = alice_session.describe()
+    assert alice_session.session_id in description
+    assert "receiving_chains" in description
+
+def test_encrypt_for_devices(alice: Account):
+    devices = [Account() for _ in range(3)]
+    sessions = []
+
+    for device in devices:
+        device.generate_one_time_keys(1)
+        one_time_key = next(iter(device.one_time_keys.values()))
+        sessions.append(alice.create_outbound_session(device.curve25519_key, one_time_key))
+
+    template = {
+        "type": "m.room_key",
+        "content": {"room_id": "!test:example.org"},
+        "sender": "@alice:example.org",
+        "keys": {"ed25519": alice.ed25519_key.to_base64()},
+    }
+
+    ciphertext = encrypt_for_devices(
+        [
+            (session, f"@user{i}:example.org", device.curve25519_key, device.ed25519_key)
+            for i, (session, device) in enumerate(zip(sessions, devices))
+        ],
+        template,
+    )
+    assert set(ciphertext) == {device.curve25519_key.to_base64() for device in devices}
+
+    content = parse_encrypted_content({
+        "algorithm": "m.olm.v1.curve25519-aes-sha2",
+        "sender_key": alice.curve25519_key.to_base64(),
+        "ciphertext": ciphertext,
+    })
+
+    for i, device in enumerate(devices):
+        message = content.message_for(device.curve25519_key).to_pre_key()
+        _, plaintext = device.create_inbound_session(alice.curve25519_key, message)
+        payload = json.loads(plaintext)
+
+        assert payload["content"] == template["content"]
+        assert payload["recipient"] == f"@user{i}:example.org"
+        assert payload["recipient_keys"] == {"ed25519": device.ed25519_key.to_base64()}
+
+    assert "recipient" not in template
+
+    with pytest.raises(ValueError, match="included more than once"):
+        encrypt_for_devices(
+            [
+                (sessions[0], "@user:example.org", devices[0].curve25519_key, devices[0].ed25519_key),
+                (sessions[0], "@user:example.org", devices[0].curve25519_key, devices[0].ed25519_key),
+            ],
+            template,
+        )
+
+    with pytest.raises(ValueError, match="included more than once"):
+        encrypt_for_devices(
+            [
+                (sessions[0], "@user:example.org", devices[0].curve25519_key, devices[0].ed25519_key),
+                (sessions[1], "@user:example.org", devices[0].curve25519_key, devices[0].ed25519_key),
+            ],
+            template,
+        )
+
+    with pytest.raises(ValueError, match="included more than once"):
+        encrypt_for_devices(
+            [
+                (sessions[0], "@user:example.org", devices[0].curve25519_key, devices[0].ed25519_key),
+                (sessions[0], "@user:example.org", devices[1].curve25519_key, devices[1].ed25519_key),
+            ],
+            template,
+        )
+
+def test_default_config(alice_session: Session):
+    assert alice_session.session_config == OlmSessionConfig.version_1()
+    assert alice_session.session_config.version == 1
+
+def test_version_2_session(alice: Account, bob: Account, pickle_key: bytes):
+    bob.generate_one_time_keys(1)
+    one_time_key = next(iter(bob.one_time_keys.values()))
+    alice_session = alice.create_outbound_session(
+        bob.curve25519_key, one_time_key, OlmSessionConfig.version_2()
+    )
+    assert alice_session.session_config.version == 2
+
+    alice_plaintext = b"It's a secret to everybody"
+    alice_message = alice_session.encrypt(alice_plaintext).to_pre_key()
+
+    bob_session, alice_decrypted = bob.create_inbound_session(alice.curve25519_key, alice_message)
+    assert alice_plaintext == alice_decrypted
+    assert bob_session.session_config == OlmSessionConfig.version_2()
+
+    bob_plaintext = b"Grumble, Grumble"
+    assert bob_plaintext == alice_session.decrypt(bob_session.encrypt(bob_plaintext))
+
+    unpickled = Session.from_pickle(alice_session.pickle(pickle_key), pickle_key)
+    assert unpickled.session_config == OlmSessionConfig.version_2()
+    assert b"Hey! Listen!" == bob_session.decrypt(unpickled.encrypt(b"Hey! Listen!"))
@@ -5,23 +5,50 @@ from .vodozemac import *  # type: ignore
 __all__ = [
     # Classes
     "Account",
+    "OneTimeKeyGenerationResult",
+    "InboundCreationResult",
     "Session", 
+    "OlmSessionConfig",
+    "OlmSessionStore",
+    "OlmDecryptionResult",
+    "SessionKeys",
     "AnyOlmMessage",
     "PreKeyMessage",
+    "NormalOlmMessage",
     "Sas",
     "EstablishedSas", 
+    "SasVerification",
+    "Ecies",
+    "EstablishedEcies",
+    "InitialMessage",
+    "EciesMessage",
+    "CheckCode",
     "GroupSession",
     "InboundGroupSession",
+    "MegolmSessionConfig",
+    "SessionOrdering",
     "SessionKey",
     "ExportedSessionKey", 
     "MegolmMessage",
     "Ed25519PublicKey",
+    "Ed25519SecretKey",
     "Ed25519Signature",
     "Curve25519PublicKey", 
     "Curve25519SecretKey",
+    "SharedSecret",
     "PkDecryption",
     "PkEncryption",
     "Message",
+    "SecretStorageKey",
+    "AttachmentEncryptor",
+    "AttachmentDecryptor",
+    "OlmEncryptedContent",
+    "MegolmEncryptedContent",
+    # Functions
+    "encrypt_key_export",
+    "decrypt_key_export",
+    "parse_encrypted_content",
+    "encrypt_for_devices",
     # Exceptions
     "KeyException",
     "SignatureException", 
@@ -33,8 +60,16 @@ __all__ = [
     "SasException", 
     "OlmDecryptionException",
     "MegolmDecryptionException",
+    "EciesException",
+    "DehydratedDeviceException",
+    "KeyExportException",
+    "RecoveryKeyException",
+    "SecretStorageException",
+    "AttachmentException",
+    "AttachmentHashMismatchException",
     "PkInvalidKeySizeException",
     "PkDecodeException",
+    "EncryptedContentException",
 ]
 
 with contextlib.suppress(ImportError):
@@ -1,18 +1,27 @@
 """Type stubs for vodozemac - Python bindings for the vodozemac Rust library."""
 
-from typing import Optional, Dict, Any, Tuple, List
+from typing import Optional, Dict, Any, Tuple, List, TypeVar, Iterator
 from typing_extensions import Self
 
 __all__ = [
-    "Account", "Session", "AnyOlmMessage", "PreKeyMessage", "Sas", "EstablishedSas", 
-    "GroupSession", "InboundGroupSession", "SessionKey", "ExportedSessionKey", 
-    "MegolmMessage", "Ed25519PublicKey", "Ed25519Signature", "Curve25519PublicKey", 
-    "Curve25519SecretKey", "PkDecryption", "PkEncryption", "Message", "KeyException",
-    "SignatureException", "DecodeException", "LibolmPickleException", "SessionKeyDecodeException",
+    "Account", "OneTimeKeyGenerationResult", "InboundCreationResult", "Session", "OlmSessionConfig",
+    "OlmSessionStore", "OlmDecryptionResult", "SessionKeys", "AnyOlmMessage", "PreKeyMessage", "NormalOlmMessage",
+    "Sas", "EstablishedSas", "SasVerification", "Ecies", "EstablishedEcies", "InitialMessage", "EciesMessage",
+    "CheckCode", "GroupSession", "InboundGroupSession", "MegolmSessionConfig", "SessionOrdering", "SessionKey",
+    "ExportedSessionKey", "MegolmMessage", "Ed25519PublicKey", "Ed25519SecretKey", "Ed25519Signature", "Curve25519PublicKey", 
+    "Curve25519SecretKey", "SharedSecret", "PkDecryption", "PkEncryption", "Message", "SecretStorageKey",
+    "AttachmentEncryptor", "AttachmentDecryptor", "OlmEncryptedContent", "MegolmEncryptedContent",
+    "encrypt_key_export", "decrypt_key_export", "parse_encrypted_content", "encrypt_for_devices",
+    "KeyException", "SignatureException", "DecodeException", "LibolmPickleException", "SessionKeyDecodeException",
     "PickleException", "SessionCreationException", "SasException", "OlmDecryptionException", 
-    "MegolmDecryptionException", "PkInvalidKeySizeException", "PkDecodeException"
+    "MegolmDecryptionException", "EciesException", "DehydratedDeviceException", "KeyExportException",
+    "RecoveryKeyException", "SecretStorageException", "AttachmentException",
+    "AttachmentHashMismatchException", "PkInvalidKeySizeException", "PkDecodeException",
+    "EncryptedContentException"
 ]
 
+JsonObject = TypeVar("JsonObject", Dict[str, Any], str)
+
 # Exceptions
 class KeyException(ValueError): ...
 class SignatureException(ValueError): ...
@@ -24,8 +33,16 @@ class SessionCreationException(ValueError): ...
 class SasException(ValueError): ...
 class OlmDecryptionException(ValueError): ...
 class MegolmDecryptionException(ValueError): ...
+class EciesException(ValueError): ...
+class DehydratedDeviceException(ValueError): ...
+class KeyExportException(ValueError): ...
+class RecoveryKeyException(ValueError): ...
+class SecretStorageException(ValueError): ...
+class AttachmentException(ValueError): ...
+class AttachmentHashMismatchException(AttachmentException): ...
 class PkInvalidKeySizeException(ValueError): ...
 class PkDecodeException(ValueError): ...
+class EncryptedContentException(ValueError): ...
 
 # Key Types
 class Ed25519PublicKey:
@@ -35,8 +52,23 @@ class Ed25519PublicKey:
     def from_base64(cls, key: str) -> Ed25519PublicKey: ...
     def to_base64(self) -> str: ...
     def verify_signature(self, message: bytes, signature: Ed25519Signature) -> None: ...
+    def verify_json(self, object: Dict[str, Any] | str, user_id: str, key_id: str) -> None: ...
     def __eq__(self, other: object) -> bool: ...
 
+class Ed25519SecretKey:
+    """An Ed25519 secret key."""
+    
+    def __init__(self) -> None: ...
+    @classmethod
+    def from_base64(cls, key: str) -> Ed25519SecretKey: ...
+    @classmethod
+    def from_bytes(cls, bytes: bytes) -> Ed25519SecretKey: ...
+    def to_base64(self) -> str: ...
+    def to_bytes(self) -> bytes: ...
+    def public_key(self) -> Ed25519PublicKey: ...
+    def sign(self, message: bytes) -> Ed25519Signature: ...
+    def sign_json(self, object: JsonObject, user_id: str, key_id: str) -> JsonObject: ...
+
 class Ed25519Signature:
     """An Ed25519 signature."""
     
@@ -63,9 +95,19 @@ class Curve25519SecretKey:
     def from_base64(cls, key: str) -> Curve25519SecretKey: ...
     @classmethod
     def from_bytes(cls, bytes: bytes) -> Curve25519SecretKey: ...
+    @classmethod
+    def from_recovery_key(cls, recovery_key: str) -> Curve25519SecretKey: ...
+    def to_recovery_key(self) -> str: ...
     def to_base64(self) -> str: ...
     def to_bytes(self) -> bytes: ...
     def public_key(self) -> Curve25519PublicKey: ...
+    def diffie_hellman(self, public_key: Curve25519PublicKey) -> SharedSecret: ...
+
+class SharedSecret:
+    """The shared secret of a Curve25519 Diffie-Hellman key exchange."""
+    
+    def to_bytes(self) -> bytes: ...
+    def __eq__(self, other: object) -> bool: ...
 
 # Session Keys
 class SessionKey:
@@ -91,6 +133,7 @@ class AnyOlmMessage:
     @classmethod
     def from_parts(cls, message_type: int, ciphertext: bytes) -> AnyOlmMessage: ...
     def to_pre_key(self) -> Optional[PreKeyMessage]: ...
+    def to_normal(self) -> Optional[NormalOlmMessage]: ...
     def to_parts(self) -> Tuple[int, bytes]: ...
 
 class PreKeyMessage:
@@ -98,8 +141,46 @@ class PreKeyMessage:
     
     @classmethod
     def from_base64(cls, message: str) -> PreKeyMessage: ...
+    @classmethod
+    def from_bytes(cls, message: bytes) -> PreKeyMessage: ...
+    def to_base64(self) -> str: ...
+    def to_bytes(self) -> bytes: ...
     def to_any(self) -> AnyOlmMessage: ...
     def session_id(self) -> str: ...
+    def session_keys(self) -> SessionKeys: ...
+    @property
+    def identity_key(self) -> Curve25519PublicKey: ...
+    @property
+    def base_key(self) -> Curve25519PublicKey: ...
+    @property
+    def one_time_key(self) -> Curve25519PublicKey: ...
+    @property
+    def message(self) -> NormalOlmMessage: ...
+    @property
+    def version(self) -> int: ...
+
+class NormalOlmMessage:
+    """A normal Olm message, sent once the session has been established."""
+    
+    @classmethod
+    def from_base64(cls, message: str) -> NormalOlmMessage: ...
+    @classmethod
+    def from_bytes(cls, message: bytes) -> NormalOlmMessage: ...
+    def to_base64(self) -> str: ...
+    def to_bytes(self) -> bytes: ...
+    def to_any(self) -> AnyOlmMessage: ...
+    @property
+    def ratchet_key(self) -> Curve25519PublicKey: ...
+    @property
+    def chain_index(self) -> int: ...
+    @property
+    def ciphertext(self) -> bytes: ...
+    @property
+    def mac(self) -> bytes: ...
+    @property
+    def version(self) -> int: ...
+    @property
+    def mac_truncated(self) -> bool: ...
 
 class MegolmMessage:
     """A Megolm encrypted message."""
@@ -112,8 +193,39 @@ class MegolmMessage:
     def to_bytes(self) -> bytes: ...
     def message_index(self) -> int: ...
     def signature(self) -> Ed25519Signature: ...
+    def ciphertext(self) -> bytes: ...
+    def mac(self) -> bytes: ...
+    def verify_signature(self, signing_key: Ed25519PublicKey) -> None: ...
 
 # Account and Session
+class OneTimeKeyGenerationResult:
+    """The one-time keys which were created and discarded by a key generation."""
+    
+    @property
+    def created(self) -> List[Curve25519PublicKey]: ...
+    @property
+    def removed(self) -> List[Curve25519PublicKey]: ...
+    @property
+    def created_key_ids(self) -> List[str]: ...
+    @property
+    def removed_key_ids(self) -> List[Optional[str]]:
+        """The IDs of the removed keys, None for keys which were already published."""
+
+class InboundCreationResult:
+    """The result of creating an inbound Olm session."""
+    
+    @property
+    def session(self) -> Session: ...
+    @property
+    def plaintext(self) -> bytes: ...
+    @property
+    def used_key_id(self) -> Optional[str]: ...
+    @property
+    def used_fallback_key(self) -> bool: ...
+    def __len__(self) -> int: ...
+    def __getitem__(self, index: int) -> Session | bytes: ...
+    def __iter__(self) -> Iterator[Session | bytes]: ...
+
 class Account:
     """An Olm account."""
     
@@ -123,6 +235,9 @@ class Account:
     @classmethod
     def from_libolm_pickle(cls, pickle: str, pickle_key: bytes) -> Account: ...
     def pickle(self, pickle_key: bytes) -> str: ...
+    @classmethod
+    def from_dehydrated_device(cls, ciphertext: str, nonce: str, key: bytes) -> Account: ...
+    def to_dehydrated_device(self, key: bytes) -> Tuple[str, str]: ...
     
     @property
     def ed25519_key(self) -> Ed25519PublicKey: ...
@@ -132,12 +247,46 @@ class Account:
     def one_time_keys(self) -> Dict[str, Curve25519PublicKey]: ...
     @property
     def max_number_of_one_time_keys(self) -> int: ...
+    @property
+    def stored_one_time_key_count(self) -> int: ...
+    @property
+    def fallback_key(self) -> Dict[str, Curve25519PublicKey]: ...
     
     def sign(self, message: bytes) -> Ed25519Signature: ...
-    def generate_one_time_keys(self, count: int) -> None: ...
+    def sign_json(self, object: JsonObject, user_id: str, key_id: str) -> JsonObject: ...
+    def device_keys(self, user_id: str, device_id: str, algorithms: Optional[List[str]] = None) -> Dict[str, Any]: ...
+    def signed_one_time_keys(self, user_id: str, device_id: str) -> Dict[str, Dict[str, Any]]: ...
+    def signed_fallback_keys(self, user_id: str, device_id: str) -> Dict[str, Dict[str, Any]]: ...
+    def generate_one_time_keys(self, count: int) -> OneTimeKeyGenerationResult: ...
+    def replenish_one_time_keys(self, server_count: int) -> OneTimeKeyGenerationResult: ...
+    def generate_fallback_key(self) -> None: ...
+    def forget_fallback_key(self) -> bool: ...
     def mark_keys_as_published(self) -> None: ...
-    def create_outbound_session(self, identity_key: Curve25519PublicKey, one_time_key: Curve25519PublicKey) -> Session: ...
-    def create_inbound_session(self, sender_key: Curve25519PublicKey, message: PreKeyMessage) -> Tuple[Session, bytes]: ...
+    def create_outbound_session(self, identity_key: Curve25519PublicKey, one_time_key: Curve25519PublicKey, session_config: Optional[OlmSessionConfig] = None) -> Session: ...
+    def create_inbound_session(self, sender_key: Curve25519PublicKey, message: PreKeyMessage) -> InboundCreationResult: ...
+
+class OlmSessionConfig:
+    """The configuration for an Olm session."""
+    
+    @classmethod
+    def version_1(cls) -> OlmSessionConfig: ...
+    @classmethod
+    def version_2(cls) -> OlmSessionConfig: ...
+    @property
+    def version(self) -> int: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class SessionKeys:
+    """The keys which were used to establish an Olm session."""
+    
+    @property
+    def identity_key(self) -> Curve25519PublicKey: ...
+    @property
+    def base_key(self) -> Curve25519PublicKey: ...
+    @property
+    def one_time_key(self) -> Curve25519PublicKey: ...
+    def session_id(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
 
 class Session:
     """An Olm session."""
@@ -149,23 +298,87 @@ class Session:
     
     @property
     def session_id(self) -> str: ...
+    @property
+    def session_config(self) -> OlmSessionConfig: ...
+    @property
+    def has_received_message(self) -> bool: ...
     
     def pickle(self, pickle_key: bytes) -> str: ...
     def session_matches(self, message: PreKeyMessage) -> bool: ...
+    def session_keys(self) -> SessionKeys: ...
+    def describe(self) -> str: ...
     def encrypt(self, plaintext: bytes) -> AnyOlmMessage: ...
     def decrypt(self, message: AnyOlmMessage) -> bytes: ...
 
+class OlmDecryptionResult:
+    """The result of decrypting a message with an OlmSessionStore."""
+
+    @property
+    def plaintext(self) -> bytes: ...
+    @property
+    def session_id(self) -> str: ...
+    @property
+    def created_session(self) -> bool: ...
+    @property
+    def used_key_id(self) -> Optional[str]: ...
+    @property
+    def used_fallback_key(self) -> bool: ...
+
+class OlmSessionStore:
+    """A store for the Olm sessions with other devices, keyed by their identity key."""
+    
+    def __init__(self, max_sessions_per_device: int = 50) -> None: ...
+    @classmethod
+    def from_pickle(cls, pickle: str, pickle_key: bytes) -> OlmSessionStore: ...
+    
+    @property
+    def max_sessions_per_device(self) -> int: ...
+    def __len__(self) -> int: ...
+    
+    def session_ids(self, identity_key: Curve25519PublicKey) -> List[str]: ...
+    def add_session(self, identity_key: Curve25519PublicKey, session: Session) -> None:
+        """Add a copy of the session to the store, the given session shouldn't be used afterwards."""
+    def get_sessions(self, identity_key: Curve25519PublicKey) -> List[Session]:
+        """Get copies of the sessions with the device, changes to them aren't reflected in the store."""
+    def remove_sessions(self, identity_key: Curve25519PublicKey) -> List[Session]: ...
+    def create_outbound_session(self, account: Account, identity_key: Curve25519PublicKey, one_time_key: Curve25519PublicKey, session_config: Optional[OlmSessionConfig] = None) -> str: ...
+    def encrypt(self, identity_key: Curve25519PublicKey, plaintext: bytes) -> Optional[AnyOlmMessage]: ...
+    def decrypt(self, account: Account, identity_key: Curve25519PublicKey, message: AnyOlmMessage) -> OlmDecryptionResult: ...
+    def pickle(self, pickle_key: bytes) -> str:
+        """Pickle the store, the whole store including the identity keys of the devices is encrypted."""
+
 # Group Sessions
+class MegolmSessionConfig:
+    """The configuration for a Megolm session."""
+    
+    @classmethod
+    def version_1(cls) -> MegolmSessionConfig: ...
+    @classmethod
+    def version_2(cls) -> MegolmSessionConfig: ...
+    @property
+    def version(self) -> int: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class SessionOrdering:
+    """How an inbound group session relates to another copy of it."""
+    
+    Equal: SessionOrdering
+    Better: SessionOrdering
+    Worse: SessionOrdering
+    Unconnected: SessionOrdering
+
 class GroupSession:
     """An outbound Megolm group session."""
     
-    def __init__(self) -> None: ...
+    def __init__(self, session_config: Optional[MegolmSessionConfig] = None) -> None: ...
     @classmethod
     def from_pickle(cls, pickle: str, pickle_key: bytes) -> GroupSession: ...
     
     @property
     def session_id(self) -> str: ...
     @property
+    def session_config(self) -> MegolmSessionConfig: ...
+    @property
     def message_index(self) -> int: ...
     @property
     def session_key(self) -> SessionKey: ...
@@ -181,18 +394,32 @@ class DecryptedMessage:
 class InboundGroupSession:
     """An inbound Megolm group session."""
     
-    def __init__(self, session_key: SessionKey) -> None: ...
+    def __init__(self, session_key: SessionKey, session_config: Optional[MegolmSessionConfig] = None) -> None: ...
     @classmethod
-    def import_session(cls, session_key: ExportedSessionKey) -> InboundGroupSession: ...
+    def import_session(cls, session_key: ExportedSessionKey, session_config: Optional[MegolmSessionConfig] = None) -> InboundGroupSession: ...
     @classmethod
-    def from_pickle(cls, pickle: str, pickle_key: bytes) -> InboundGroupSession: ...
+    def from_pickle(cls, pickle: str, pickle_key: bytes, highest_decrypted_index: Optional[int] = None) -> InboundGroupSession:
+        """Restore a session from a pickle, along with the highest_decrypted_index stored next to it."""
     
     @property
     def session_id(self) -> str: ...
     @property
+    def signing_key(self) -> Ed25519PublicKey: ...
+    @property
+    def session_config(self) -> Optional[MegolmSessionConfig]:
+        """The config this session was created with, None if it was restored from a pickle."""
+    @property
     def first_known_index(self) -> int: ...
+    @property
+    def highest_decrypted_index(self) -> Optional[int]:
+        """The highest decrypted message index, which isn't part of the pickle."""
     
     def export_at(self, index: int) -> Optional[ExportedSessionKey]: ...
+    def advance_to(self, index: int) -> bool: ...
+    def has_ratchet_at(self, index: int) -> bool: ...
+    def compare(self, other: InboundGroupSession) -> SessionOrdering: ...
+    def connected(self, other: InboundGroupSession) -> bool: ...
+    def merge(self, other: InboundGroupSession) -> Optional[InboundGroupSession]: ...
     def decrypt(self, message: MegolmMessage) -> DecryptedMessage: ...
     def pickle(self, pickle_key: bytes) -> str: ...
 
@@ -221,6 +448,102 @@ class SasBytes:
     @property
     def decimals(self) -> Tuple[int, int, int]: ...
 
+class SasVerification:
+    """An interactive m.sas.v1 key verification with another device.
+
+    All the methods return the events which need to be sent to the other
+    side as a list of (event_type, content) tuples.
+    """
+
+    def __init__(
+        self,
+        own_user_id: str,
+        own_device_id: str,
+        own_keys: Dict[str, str],
+        other_user_id: str,
+        other_device_id: str,
+        other_keys: Dict[str, str],
+        transaction_id: str,
+    ) -> None: ...
+    @property
+    def transaction_id(self) -> str: ...
+    @property
+    def state(self) -> str: ...
+    @property
+    def we_started(self) -> bool: ...
+    @property
+    def is_verified(self) -> bool: ...
+    @property
+    def is_cancelled(self) -> bool: ...
+    @property
+    def cancel_code(self) -> Optional[str]: ...
+    @property
+    def cancel_reason(self) -> Optional[str]: ...
+    @property
+    def short_authentication_string(self) -> List[str]: ...
+    @property
+    def emoji_indices(self) -> Optional[List[int]]: ...
+    @property
+    def decimals(self) -> Optional[Tuple[int, int, int]]: ...
+    @property
+    def verified_keys(self) -> List[str]: ...
+    def start(self) -> List[Tuple[str, Dict[str, Any]]]: ...
+    def accept(self) -> List[Tuple[str, Dict[str, Any]]]: ...
+    def confirm(self) -> List[Tuple[str, Dict[str, Any]]]: ...
+    def cancel(
+        self, code: str = "m.user", reason: str = "The user cancelled the verification"
+    ) -> List[Tuple[str, Dict[str, Any]]]: ...
+    def receive_event(
+        self, event_type: str, content: JsonObject
+    ) -> List[Tuple[str, Dict[str, Any]]]: ...
+
+# ECIES (secure channel for QR code login)
+class Ecies:
+    """An unestablished ECIES channel."""
+    
+    def __init__(self, info: Optional[str] = None) -> None: ...
+    @property
+    def public_key(self) -> Curve25519PublicKey: ...
+    def establish_outbound_channel(self, their_public_key: Curve25519PublicKey, initial_plaintext: bytes) -> Tuple[EstablishedEcies, InitialMessage]: ...
+    def establish_inbound_channel(self, message: InitialMessage) -> Tuple[EstablishedEcies, bytes]: ...
+
+class EstablishedEcies:
+    """An established ECIES channel."""
+    
+    @property
+    def public_key(self) -> Curve25519PublicKey: ...
+    @property
+    def check_code(self) -> CheckCode: ...
+    def encrypt(self, plaintext: bytes) -> EciesMessage: ...
+    def decrypt(self, message: EciesMessage) -> bytes: ...
+
+class CheckCode:
+    """The check code of an established ECIES channel."""
+    
+    def as_bytes(self) -> bytes: ...
+    def to_digit(self) -> int: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class InitialMessage:
+    """The initial message of an ECIES channel."""
+    
+    @classmethod
+    def from_base64(cls, message: str) -> InitialMessage: ...
+    def to_base64(self) -> str: ...
+    @property
+    def public_key(self) -> Curve25519PublicKey: ...
+    @property
+    def ciphertext(self) -> bytes: ...
+
+class EciesMessage:
+    """A message sent over an established ECIES channel."""
+    
+    @classmethod
+    def from_base64(cls, message: str) -> EciesMessage: ...
+    def to_base64(self) -> str: ...
+    @property
+    def ciphertext(self) -> bytes: ...
+
 # PK Encryption (Public Key Encryption)
 class Message:
     """A message encrypted using PK encryption."""
@@ -243,10 +566,103 @@ class PkDecryption:
     @property
     def public_key(self) -> Curve25519PublicKey: ...
     def decrypt(self, message: Message) -> bytes: ...
+    def decrypt_backup_session(
+        self, session_data: JsonObject, session_config: Optional[MegolmSessionConfig] = None
+    ) -> Tuple[InboundGroupSession, Dict[str, Any]]: ...
 
 class PkEncryption:
     """PK encryption object."""
     
     @classmethod
     def from_key(cls, key: Curve25519PublicKey) -> PkEncryption: ...
-    def encrypt(self, message: bytes) -> Message: ... 
\ No newline at end of file
+    def encrypt(self, message: bytes) -> Message: ...
+    def encrypt_backup_session(
+        self,
+        session: InboundGroupSession,
+        sender_key: Curve25519PublicKey,
+        forwarding_chain: List[str],
+        sender_claimed_keys: Dict[str, str],
+    ) -> Dict[str, Any]: ... 
+
+# Secret storage
+class SecretStorageKey:
+    """A secret storage key, using the m.secret_storage.v1.aes-hmac-sha2 algorithm."""
+
+    def __init__(self) -> None: ...
+    @classmethod
+    def from_recovery_key(cls, recovery_key: str) -> SecretStorageKey: ...
+    @classmethod
+    def from_passphrase(cls, passphrase: str, salt: str, iterations: int) -> SecretStorageKey:
+        """Derive a key from a passphrase, more than 5,000,000 iterations are rejected."""
+    def to_recovery_key(self) -> str: ...
+    def encrypt_secret(self, name: str, plaintext: str) -> Dict[str, str]: ...
+    def decrypt_secret(self, name: str, encrypted: JsonObject) -> str: ...
+    def check_key(self) -> Dict[str, str]: ...
+    def verify_check_key(self, key_description: JsonObject) -> bool: ...
+
+# Attachments
+class AttachmentEncryptor:
+    """Encrypts an attachment chunk by chunk."""
+
+    def __init__(self) -> None: ...
+    def update(self, chunk: bytes) -> bytes: ...
+    def finalize(self) -> Dict[str, Any]: ...
+
+class AttachmentDecryptor:
+    """Decrypts an attachment chunk by chunk, checking its hash in finalize()."""
+
+    def __init__(self, encrypted_file: JsonObject) -> None: ...
+    def update(self, chunk: bytes) -> bytes: ...
+    def finalize(self) -> None: ...
+
+# Encrypted event content
+class OlmEncryptedContent:
+    """The content of an m.room.encrypted event using the m.olm.v1.curve25519-aes-sha2 algorithm."""
+
+    def __init__(self, sender_key: Curve25519PublicKey, ciphertext: Dict[str, AnyOlmMessage]) -> None: ...
+    @property
+    def algorithm(self) -> str: ...
+    @property
+    def sender_key(self) -> Curve25519PublicKey: ...
+    @property
+    def ciphertext(self) -> Dict[str, AnyOlmMessage]: ...
+    def message_for(self, identity_key: Curve25519PublicKey) -> Optional[AnyOlmMessage]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+
+class MegolmEncryptedContent:
+    """The content of an m.room.encrypted event using the m.megolm.v1.aes-sha2 algorithm."""
+
+    def __init__(self, ciphertext: MegolmMessage, session_id: str, sender_key: Optional[Curve25519PublicKey] = None, device_id: Optional[str] = None) -> None: ...
+    @property
+    def algorithm(self) -> str: ...
+    @property
+    def ciphertext(self) -> MegolmMessage: ...
+    @property
+    def session_id(self) -> str: ...
+    @property
+    def sender_key(self) -> Optional[Curve25519PublicKey]: ...
+    @property
+    def device_id(self) -> Optional[str]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+
+def parse_encrypted_content(content: JsonObject) -> OlmEncryptedContent | MegolmEncryptedContent:
+    """Parse the content of an m.room.encrypted event."""
+
+def encrypt_for_devices(
+    sessions: List[Tuple[Session, str, Curve25519PublicKey, Ed25519PublicKey]],
+    plaintext_template: JsonObject,
+) -> Dict[str, Dict[str, Any]]:
+    """Encrypt the plaintext template for many devices at once, returning the m.olm.v1.curve25519-aes-sha2 ciphertext map.
+
+    Raises a ValueError if a device or a session is included more than once.
+    """
+
+# Key export
+def encrypt_key_export(json: str, passphrase: str, rounds: int = 500000) -> str:
+    """Encrypt a JSON-encoded list of room keys into the Matrix key export format."""
+
+def decrypt_key_export(data: str, passphrase: str) -> str:
+    """Decrypt a Matrix key export, returning the JSON-encoded list of room keys.
+
+    Exports which use more than 5,000,000 PBKDF2 rounds are rejected.
+    """